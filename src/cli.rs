@@ -1,21 +1,73 @@
-use clap::Parser;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use clap::error::ErrorKind;
+use clap::{ColorChoice, CommandFactory, Parser, ValueHint};
 use clap_verbosity_flag::Verbosity;
+use env_logger::WriteStyle;
 
 use crate::commands::Commands;
 
+/// Exit codes of the CLI, scripts and CI can rely on them.
+pub mod exit_code {
+    /// The command succeeded.
+    pub const SUCCESS: u8 = 0;
+    /// The command failed: invalid configuration, unreadable chapters, I/O errors...
+    pub const FAILURE: u8 = 1;
+    /// Invalid arguments, reported by clap.
+    pub const USAGE: u8 = 2;
+    /// There's no `book.toml` where the book was expected.
+    pub const BOOK_NOT_FOUND: u8 = 3;
+    /// mdbook-killer crashed, which is always a bug.
+    pub const INTERNAL: u8 = 101;
+}
+
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0    Success
+  1    The command failed (invalid configuration, unreadable chapters, I/O errors...)
+  2    Invalid arguments
+  3    No book.toml found
+  101  Internal error";
+
 // Parse commands and arguments from the CLI
 #[derive(Debug, Parser)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, after_long_help = EXIT_CODES_HELP)]
 pub struct Cli {
     #[clap(flatten)]
     verbose: Verbosity,
 
+    /// When to color the output, `auto` disables colors when stderr isn't a terminal or
+    /// `NO_COLOR` is set, and enables them when `FORCE_COLOR` is set
+    #[clap(long, value_enum, global = true, default_value_t)]
+    color: ColorChoice,
+
+    /// Configuration file of the book, instead of the `book.toml` of its directory. The book
+    /// root is the directory of the file
+    #[clap(long, global = true, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    config: Option<PathBuf>,
+
+    /// Print the bundled stylesheet, the baseline of theme overrides, and exit
+    #[clap(long, exclusive = true)]
+    pub print_theme: bool,
+
     #[clap(subcommand)]
-    pub commands: Commands,
+    pub commands: Option<Commands>,
+}
+
+impl Cli {
+    /// The configuration file given with `--config`, the commands read the `book.toml` of the
+    /// book directory without it.
+    pub fn config_file(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
 }
 
 pub fn get_cli() -> Cli {
     let cli = Cli::parse();
+    // Only `--print-theme` goes without a command
+    if cli.commands.is_none() && !cli.print_theme {
+        Cli::command().error(ErrorKind::MissingSubcommand, "a command is required").exit();
+    }
     let log_filter = cli.verbose.log_level_filter();
 
     // initialize logger
@@ -23,9 +75,26 @@ pub fn get_cli() -> Cli {
         .default_format()
         .parse_default_env()
         .filter_level(log_filter)
+        .write_style(write_style(cli.color))
         .init();
 
     log::info!("Initialized Logger with Level: {log_filter:?}");
 
     cli
 }
+
+/// Whether the logs are colored, `auto` follows the `NO_COLOR` and `FORCE_COLOR` conventions
+/// and otherwise colors them only on a terminal, so the logs of CI don't have escape codes.
+fn write_style(color: ColorChoice) -> WriteStyle {
+    let is_set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    match color {
+        ColorChoice::Always => WriteStyle::Always,
+        ColorChoice::Never => WriteStyle::Never,
+        ColorChoice::Auto if is_set("NO_COLOR") => WriteStyle::Never,
+        ColorChoice::Auto if std::env::var_os("FORCE_COLOR").is_some_and(|value| value != "0") => {
+            WriteStyle::Always
+        }
+        ColorChoice::Auto if std::io::stderr().is_terminal() => WriteStyle::Always,
+        ColorChoice::Auto => WriteStyle::Never,
+    }
+}
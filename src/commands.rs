@@ -1,15 +1,31 @@
-use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, Subcommand, ValueHint};
 use clap_complete::{generate_to, Shell};
 
 use crate::cli::Cli;
-use crate::models::Config;
+use crate::diagnostic::{self, MessageFormat};
+use crate::error::BuildError;
+use crate::models::book::Book;
+use crate::models::{book_root, Config};
 
 mod build;
+mod check;
+mod complete;
+#[cfg(feature = "dev-tools")]
+mod gen_fixture;
 mod init;
+mod migrate;
+mod render;
+mod serve;
+mod stats;
+mod watch;
+
+pub use build::bundled_css;
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
@@ -21,10 +37,34 @@ pub enum Commands {
         /// Sets the book title
         #[clap(long)]
         title: Option<String>,
+        /// Starting structure of the book
+        #[clap(long, value_enum, default_value_t)]
+        template: init::Template,
+        /// Initializes a git repository ignoring the build output
+        #[clap(long)]
+        init_git: bool,
         /// Directory to create the book in
         #[clap(value_hint = ValueHint::AnyPath)]
         dir: PathBuf,
     },
+    /// Translates the `book.toml` of an mdBook project into a configuration of mdbook-killer,
+    /// reporting the options it couldn't translate. The sources are left untouched
+    Migrate {
+        /// File to write the configuration to, relative to the project
+        #[clap(
+            long,
+            short,
+            value_hint = ValueHint::FilePath,
+            default_value = migrate::DEFAULT_OUTPUT
+        )]
+        output: PathBuf,
+        /// Overwrites the configuration file when it already exists
+        #[clap(long)]
+        force: bool,
+        /// Root directory of the mdBook project
+        #[clap(value_hint = ValueHint::DirPath, default_value = ".")]
+        dir: PathBuf,
+    },
     /// Builds a book from its markdown files
     Build {
         /// Opens the compiled book in a web browser
@@ -36,10 +76,67 @@ pub enum Commands {
         /// or defaults to `./book`.
         #[clap(long, short, value_hint = ValueHint::DirPath)]
         dest_dir: Option<PathBuf>,
+        /// Prints how long each phase of the build took
+        #[clap(long)]
+        timings: bool,
+        /// How warnings and errors are printed
+        #[clap(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
+        /// Only writes the chapters whose slug matches this glob, can be repeated
+        #[clap(long, value_name = "GLOB")]
+        only: Vec<String>,
+        /// How many languages are built at the same time, defaults to the number of cores
+        #[clap(long, short, value_name = "N")]
+        jobs: Option<usize>,
+        /// Builds in a temporary directory replacing the output once done, a failed build
+        /// keeps the previous output
+        #[clap(long, conflicts_with = "only")]
+        atomic: bool,
+        /// Fails the build when there's any warning, reporting them as errors
+        #[clap(long)]
+        deny_warnings: bool,
+        /// Only writes the search index, `searchindex.json`, skipping the pages and assets. For
+        /// search interfaces hosted elsewhere
+        #[clap(long, conflicts_with_all = ["only", "atomic", "timings"])]
+        search_index_only: bool,
+        /// Renders the markdown of the current directory with the default settings when
+        /// there's no `book.toml`, instead of failing
+        #[clap(long)]
+        allow_missing_config: bool,
+        /// Root directory for the book
+        #[clap(value_hint = ValueHint::DirPath)]
+        dir: PathBuf,
+    },
+    /// Checks the book for problems without building it, like untranslated chapters
+    Check {
+        /// Only checks the translation to this language
+        #[clap(long)]
+        language: Option<String>,
+        /// How warnings and errors are printed
+        #[clap(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
         /// Root directory for the book
         #[clap(value_hint = ValueHint::DirPath)]
         dir: PathBuf,
     },
+    /// Prints the number of chapters, words, code blocks and images of the book
+    Stats {
+        /// Root directory for the book
+        #[clap(value_hint = ValueHint::DirPath)]
+        dir: PathBuf,
+    },
+    /// Renders a single markdown document to HTML on stdout, without navigation
+    Render {
+        /// Reads the markdown from stdin
+        #[clap(long)]
+        stdin: bool,
+        /// Theme directory overriding the bundled theme
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        theme: Option<PathBuf>,
+        /// Markdown file to render
+        #[clap(value_hint = ValueHint::FilePath, required_unless_present = "stdin", conflicts_with = "stdin")]
+        file: Option<PathBuf>,
+    },
     /// Deletes a built book
     Clean {
         /// Root directory for the book
@@ -60,6 +157,16 @@ pub enum Commands {
         #[clap(value_hint = ValueHint::DirPath)]
         out_dir: PathBuf,
     },
+    /// Prints the languages or chapters of the book in the current directory, used by the
+    /// completion scripts
+    #[clap(name = "__complete", hide = true)]
+    Complete {
+        #[clap(value_enum)]
+        candidates: complete::Candidates,
+        /// Word being completed
+        #[clap(default_value = "")]
+        prefix: String,
+    },
     /// Watches a book's files and rebuilds it on changes
     Watch {
         /// Opens the compiled book in a web browser
@@ -71,13 +178,17 @@ pub enum Commands {
         /// or defaults to `./book`.
         #[clap(long, short, value_hint = ValueHint::DirPath)]
         dest_dir: Option<PathBuf>,
+        /// Checks the sources for changes every interval, like `500ms` or `2s`, instead of
+        /// waiting for notifications. Needed on network and container filesystems (Docker
+        /// volumes, WSL mounts) that don't send them.
+        #[clap(long, value_name = "INTERVAL", value_parser = watch::parse_interval)]
+        poll: Option<Duration>,
         /// Root directory for the book
         #[clap(value_hint = ValueHint::AnyPath)]
         dir: PathBuf,
     },
-    /// Serves a book at http://localhost:3000, and rebuilds it on changes
+    /// Serves a book at http://localhost:3000, and rebuilds it on changes with `--poll`
     Serve {
-        ///
         #[clap(long, short)]
         open: bool,
         /// Port to use for HTTP connections
@@ -92,16 +203,29 @@ pub enum Commands {
         /// Hostname to listen on for HTTP connections
         #[clap(long, short = 'n', default_value = "localhost", value_hint = ValueHint::Hostname)]
         hostname: Option<String>,
+        /// Listens on all interfaces so the book is reachable from other devices in the LAN,
+        /// same as `--hostname 0.0.0.0`
+        #[clap(long, conflicts_with = "hostname")]
+        lan: bool,
+        /// Uses the next free port when the requested one is in use
+        #[clap(long)]
+        retry_port: bool,
+        /// Rebuilds the book when its sources change, checking them every interval, like
+        /// `500ms` or `2s`
+        #[clap(long, value_name = "INTERVAL", value_parser = watch::parse_interval)]
+        poll: Option<Duration>,
+        /// Renders the markdown of the current directory with the default settings when
+        /// there's no `book.toml`, instead of failing
+        #[clap(long)]
+        allow_missing_config: bool,
         /// Root directory for the book
         #[clap(value_hint = ValueHint::DirPath)]
         dir: PathBuf,
     },
     /// Tests that a book's Rust code samples compile
     Test {
-        ///
         #[clap(long, short)]
         open: bool,
-        ///
         #[clap(long, short)]
         chapter: Option<String>,
         /// A comma-separated list of directories to add to the crate search path when building tests
@@ -111,6 +235,17 @@ pub enum Commands {
         #[clap(value_hint = ValueHint::DirPath)]
         dir: PathBuf,
     },
+    /// Writes a synthetic book with many chapters, to reproduce performance issues
+    #[cfg(feature = "dev-tools")]
+    #[clap(name = "gen-fixture", hide = true)]
+    GenFixture {
+        /// Number of chapters
+        #[clap(long, default_value = "1000")]
+        chapters: usize,
+        /// Directory to write the book to
+        #[clap(value_hint = ValueHint::DirPath)]
+        dir: PathBuf,
+    },
 }
 
 use once_cell::sync::Lazy;
@@ -119,67 +254,245 @@ use tokio::sync::RwLock;
 pub static CONFIG: Lazy<RwLock<Option<Config>>> = Lazy::new(|| RwLock::new(None));
 
 impl Commands {
-    pub async fn execute(&self) -> Result<()> {
+    /// Runs the command on the book configured by `config_file`, else by the `book.toml` of the
+    /// book directory the command is given.
+    pub async fn execute(&self, config_file: Option<&Path>) -> Result<()> {
+        // The commands without a book directory work in the current one
+        let current = config_file_of(config_file, Path::new("."));
         match self {
             Commands::Completions { shell, out_dir } => {
                 let mut cmd = Cli::command_for_update();
                 let name = cmd.get_name().to_string();
-                generate_to(*shell, &mut cmd, name, out_dir).unwrap();
+                let script = generate_to(*shell, &mut cmd, &name, out_dir)
+                    .with_context(|| "Unable to write the completions")?;
+                if let Some(dynamic) = complete::dynamic_script(*shell, &name) {
+                    std::fs::OpenOptions::new()
+                        .append(true)
+                        .open(&script)
+                        .and_then(|mut file| file.write_all(dynamic.as_bytes()))
+                        .with_context(|| format!("Unable to write {}", script.display()))?;
+                }
             }
+            Commands::Complete { candidates, prefix } => complete::execute(*candidates, prefix, &current)?,
             Commands::Clean { dir, dest_dir } => {
-                let config = Config::from_disk("./book.toml")?;
-                let dir_to_remove = match dest_dir {
-                    Some(dest_dir) => dest_dir.into(),
-                    None => match config.build.as_ref().map(|b| b.build_dir.clone()) {
-                        Some(build_dir) => config.book.src.join(&build_dir),
-                        None => config.book.src.join(&dir),
-                    },
-                };
+                let config_file = config_file_of(config_file, dir);
+                let config = Config::from_disk(&config_file)?;
+                let root = book_root(&config_file);
+                let dir_to_remove = resolve_output_dir(&config, root, dest_dir.as_deref());
 
                 if dir_to_remove.exists() {
                     std::fs::remove_dir_all(&dir_to_remove)
                         .with_context(|| "Unable to remove the build directory")?;
                 }
             }
-            Commands::Init { theme, title, dir } => {
-                init::execute(theme.clone(), title.clone(), dir)?
+            Commands::Check { language, message_format, dir } => {
+                diagnostic::set_format(*message_format);
+                let config_file = config_file_of(config_file, dir);
+                let config = Config::from_disk(&config_file)?;
+                check::execute(&config, book_root(&config_file), language.as_deref())?
+            }
+            Commands::Stats { dir } => {
+                let config_file = config_file_of(config_file, dir);
+                let config = Config::from_disk(&config_file)?;
+                let book = Book::from_config(config, book_root(&config_file))?;
+                stats::execute(&book)?
+            }
+            Commands::Render { stdin: _, theme, file } => {
+                render::execute(file.as_deref(), theme.as_deref(), &current).await?
+            }
+            Commands::Init {
+                theme,
+                title,
+                template,
+                init_git,
+                dir,
+            } => {
+                init::execute(theme.clone(), title.clone(), *template, *init_git, dir)?
             }
+            Commands::Migrate { output, force, dir } => migrate::execute(dir, output, *force)?,
             Commands::Build {
-                open,
+                open: _,
                 dest_dir,
+                timings,
+                message_format,
+                only,
+                jobs,
+                atomic,
+                deny_warnings,
+                search_index_only,
+                allow_missing_config,
                 dir,
             } => {
-                let config = read_to_string("./book.toml").expect("Fallo al abrir el ./book.toml");
-                let config: Config =
-                    toml::from_str(&config).expect("Fallo al parsear el archivo book.toml");
-                println!("Config {:?}", config);
+                diagnostic::set_format(*message_format);
+                if *deny_warnings {
+                    diagnostic::deny_warnings();
+                }
+                let start = Instant::now();
+                let config_file = config_file_of(config_file, dir);
+                let root = book_root(&config_file);
+                let config = load_config(&config_file, *allow_missing_config)?;
+                log::debug!("Config {:?}", config);
+
+                if *search_index_only {
+                    _ = CONFIG.write().await.insert(config.clone());
+                    let out = resolve_output_dir(&config, root, dest_dir.as_deref());
+                    let out = build::output_dir(&config, &out, "html");
+                    fs::create_dir_all(&out)
+                        .with_context(|| format!("Unable to create {}", out.display()))?;
+                    let book = Book::from_config(config, root)?;
+                    return build::write_search_index(&book, &out);
+                }
+
+                let mut phases = build::Timings::default();
+                phases.record("config load", start.elapsed());
 
                 _ = CONFIG.write().await.insert(config.clone());
 
-                let default_language = config.default_language();
+                let out = resolve_output_dir(&config, root, dest_dir.as_deref());
+                let build_config = config.build.clone().unwrap_or_default();
+                build::run_hooks("pre-build", &build_config.pre_build, root, &out)?;
 
-                build::execute(default_language, config.book.languages).await?
+                let start = Instant::now();
+                let book = Book::from_config(config, root)?;
+                phases.record("chapter discovery", start.elapsed());
+                phases.append(build::execute(&book, &out, only, *jobs, *atomic).await?);
+                build::run_hooks("post-build", &build_config.post_build, root, &out)?;
+                phases.log_slowest_pages();
+                if *timings {
+                    phases.print();
+                }
+                match diagnostic::denied_warnings() {
+                    0 => {}
+                    1 => anyhow::bail!("The build has a warning, denied by --deny-warnings"),
+                    count => anyhow::bail!("The build has {count} warnings, denied by --deny-warnings"),
+                }
             }
             Commands::Watch {
-                open,
+                open: _,
                 dest_dir,
+                poll,
                 dir,
-            } => {}
+            } => {
+                // Only polling is implemented, file notifications aren't available yet
+                let Some(interval) = poll else {
+                    anyhow::bail!(
+                        "File change notifications aren't supported yet, \
+                         pass --poll <INTERVAL> to check the sources periodically instead"
+                    );
+                };
+                let config_file = config_file_of(config_file, dir);
+                let root = book_root(&config_file);
+                let config = Config::from_disk(&config_file)?;
+                _ = CONFIG.write().await.insert(config.clone());
+
+                let out = resolve_output_dir(&config, root, dest_dir.as_deref());
+                let polling = watch::Polling {
+                    config_file: &config_file,
+                    allow_missing_config: false,
+                    interval: *interval,
+                };
+                watch::execute(polling, root, &out).await?
+            }
             Commands::Serve {
-                open,
+                open: _,
                 port,
                 dest_dir,
                 hostname,
+                lan,
+                retry_port,
+                poll,
+                allow_missing_config,
                 dir,
-            } => {}
-            Commands::Test {
-                open,
-                chapter,
-                library_path,
-                dir,
-            } => {}
+            } => {
+                let config_file = config_file_of(config_file, dir);
+                let root = book_root(&config_file);
+                let config = load_config(&config_file, *allow_missing_config)?;
+                _ = CONFIG.write().await.insert(config.clone());
+
+                let hostname = match (lan, hostname) {
+                    (true, _) => "0.0.0.0",
+                    (false, hostname) => hostname.as_deref().unwrap_or("localhost"),
+                };
+
+                let out = resolve_output_dir(&config, root, dest_dir.as_deref());
+                let watch = poll.map(|interval| watch::Polling {
+                    config_file: &config_file,
+                    allow_missing_config: *allow_missing_config,
+                    interval,
+                });
+                let port = port.unwrap_or(3000);
+                serve::execute(&config, root, &out, hostname, port, *retry_port, watch).await?
+            }
+            Commands::Test { .. } => anyhow::bail!("The test command isn't implemented yet"),
+            #[cfg(feature = "dev-tools")]
+            Commands::GenFixture { chapters, dir } => gen_fixture::execute(*chapters, dir)?,
         }
 
         Ok(())
     }
 }
+
+/// Builds the book whose `book.toml` is in `root` into `out`, like the `build` command without
+/// its hooks, for the programs using mdbook-killer as a library. Fails with a [`BuildError`]
+/// telling what went wrong.
+pub async fn build_book(root: &Path, out: &Path) -> Result<(), BuildError> {
+    let book = Book::load(root)?;
+    _ = CONFIG.write().await.insert(book.config.clone());
+
+    build::execute(&book, out, &[], None, false).await?;
+    Ok(())
+}
+
+/// The configuration file of the book in `dir`: `config_file` when `--config` is given, else its
+/// `book.toml`. The book root is derived from it once, see [`book_root`].
+fn config_file_of(config_file: Option<&Path>, dir: &Path) -> PathBuf {
+    config_file.map_or_else(|| dir.join("book.toml"), Path::to_path_buf)
+}
+
+/// Reads `config_file`, with `allow_missing` its absence means the default configuration, see
+/// [`Config::from_disk_or_default`].
+fn load_config(config_file: &Path, allow_missing: bool) -> Result<Config> {
+    if allow_missing {
+        Config::from_disk_or_default(config_file)
+    } else {
+        Config::from_disk(config_file)
+    }
+}
+
+/// Directory a book is written to: `dest_dir`, else `build.build-dir` from `book.toml`, else
+/// `book`. Relative paths are relative to the book root `dir`, see [`book_root`].
+/// Every command touching the output goes through here so they agree on its location.
+pub fn resolve_output_dir(config: &Config, dir: &Path, dest_dir: Option<&Path>) -> PathBuf {
+    match dest_dir {
+        Some(dest_dir) => dir.join(dest_dir),
+        None => dir.join(config.build.clone().unwrap_or_default().build_dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::build_config::BuildConfig;
+
+    #[test]
+    fn output_dir_is_the_same_for_every_command() {
+        let mut config = Config::default();
+        let dir = Path::new("my-book");
+
+        assert_eq!(resolve_output_dir(&config, dir, None), dir.join("book"));
+
+        config.build = Some(BuildConfig {
+            build_dir: PathBuf::from("site"),
+            ..Default::default()
+        });
+        assert_eq!(resolve_output_dir(&config, dir, None), dir.join("site"));
+        assert_eq!(
+            resolve_output_dir(&config, dir, Some(Path::new("public"))),
+            dir.join("public")
+        );
+        assert_eq!(
+            resolve_output_dir(&config, dir, Some(Path::new("/tmp/public"))),
+            PathBuf::from("/tmp/public")
+        );
+    }
+}
@@ -1,161 +1,807 @@
-use crate::default_theme::chapterpage::{ChapterPage, ChapterPageProps};
+use crate::default_theme::errorpage::{StandalonePage, StandalonePageProps, NOT_FOUND};
 use crate::default_theme::custom_component::{CustomComponent, CustomComponentProps};
 use crate::default_theme::homepage::{Homepage, HomepageProps};
-use crate::models::lang_config::LanguageConfig;
-use crate::models::Chapter;
-use crate::renderer::ssg::Ssg;
+use crate::default_theme::links::page_file;
+use crate::default_theme::overrides::ThemeOverrides;
+use crate::default_theme::printpage::{PrintPage, PrintPageProps};
+use crate::models::rust_config::RustEdition;
+use crate::models::book::{landing_chapter, Book, LanguageSection, NOT_FOUND_FILE, OFFLINE_FILE};
+use crate::models::preprocessors_config::{CodeTheme, HtmlPrint, Robots};
+use crate::models::{Chapter, Config, SectionNumber};
+use crate::preprocessors;
+use crate::diagnostic::Diagnostic;
+use crate::error::BuildError;
+use crate::renderer::dates::DateSettings;
+use crate::renderer::highlight;
+use crate::renderer::markdown::{excerpt_markdown, largest_code_block, render_markdown, MarkdownSettings};
+use crate::renderer::output;
+use crate::renderer::rewrite::{link_host, RewriteSettings};
+use crate::renderer::ssg::{SiteContext, Ssg};
 use anyhow::{anyhow, Context, Result};
-use leptos::html::AnyElement;
-use leptos::leptos_dom::{ComponentRepr, Element};
-use leptos::ssr::render_to_string;
-use leptos::{component, document, view, Children, HtmlElement, IntoView};
+use chrono::Datelike;
 use std::collections::HashMap;
-use std::fs::{self, read_to_string, ReadDir};
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
-use gray_matter::engine::YAML;
-use gray_matter::Matter;
-use tailwind_css::TailwindBuilder;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use sha2::{Digest, Sha256};
 
-static CSS_FILE: &'static str = include_str!("../../leptos_start.css");
+mod assets;
+mod atomic;
+mod fonts;
+mod hooks;
+mod hosting;
+mod offline;
+mod redirects;
+mod robots;
+mod search;
+mod timings;
 
+pub use hooks::run as run_hooks;
+pub use timings::Timings;
+
+pub(super) static CSS_FILE: &str = include_str!("../../leptos_start.css");
+
+/// Builds the loaded `book` into `out` in every format of its `[output.*]` sections, returning
+/// how long each phase took. A book with several outputs gets a subdirectory for each one.
+///
+/// With `only` globs, just the chapters whose slug matches one of them are written. Their
+/// navigation still lists every chapter, so the links to the rest of the book keep working.
+///
+/// Up to `jobs` languages are built at the same time, as many as the machine has cores when
+/// `None`. The root homepage is generated once they're done.
+///
+/// An `atomic` build is written to a temporary directory that replaces `out` once it succeeded,
+/// a failed build leaves the previous output untouched.
 pub async fn execute(
-    default_language: Option<String>,
-    languages: Option<Vec<String>>,
-) -> Result<()> {
-    println!("{languages:?}");
+    book: &Book,
+    out: &Path,
+    only: &[String],
+    jobs: Option<usize>,
+    atomic: bool,
+) -> Result<Timings> {
+    if !atomic {
+        return build_outputs(book, out, only, jobs).await;
+    }
+
+    let staging = atomic::staging_dir(out);
+    // Left by a build that was interrupted
+    atomic::remove_dir(&staging)?;
+    let result = match build_outputs(book, &staging, only, jobs).await {
+        Ok(timings) => atomic::replace(&staging, out).map(|()| timings),
+        Err(error) => Err(error),
+    };
+    if result.is_err() {
+        _ = atomic::remove_dir(&staging);
+    }
+    result
+}
+
+/// Writes every output of `book` to `out`.
+async fn build_outputs(book: &Book, out: &Path, only: &[String], jobs: Option<usize>) -> Result<Timings> {
+    let outputs = book.config.outputs();
+    let mut timings = Timings::default();
+
+    for name in &outputs {
+        let dir = output_dir(&book.config, out, name);
+        fs::create_dir_all(&dir).with_context(|| format!("Unable to create {}", dir.display()))?;
+        if name == "html" {
+            timings.append(build_html(book, &dir, only, jobs).await?);
+            continue;
+        }
+
+        let start = Instant::now();
+        match output::renderer(name) {
+            Some(renderer) => renderer.render(book, &dir)?,
+            None => Diagnostic::warning(format!("skipped the unknown `[output.{name}]`")).emit(),
+        }
+        timings.record("other outputs", start.elapsed());
+    }
+
+    Ok(timings)
+}
+
+/// Directory the `name` output is written to, `out` itself for a book with a single output.
+pub fn output_dir(config: &Config, out: &Path, name: &str) -> PathBuf {
+    if config.outputs().len() > 1 {
+        out.join(name)
+    } else {
+        out.to_path_buf()
+    }
+}
 
-    let languages = languages.or(Some(vec!["".to_string()])).unwrap();
+async fn build_html(book: &Book, out: &Path, only: &[String], jobs: Option<usize>) -> Result<Timings> {
+    let only = chapter_filter(only)?;
+    let mut timings = Timings::default();
+    let config = &book.config;
+    let root = book.root.as_path();
+    let default_language = config.default_language();
 
-    let out = Path::new("./out/book");
     if !out.exists() {
         std::fs::create_dir_all(out).expect("Cannot create 'out' directory");
     }
 
-    let ssg = Ssg::new(out);
-    std::fs::write("./out/book/style.css", CSS_FILE)?;
+    let theme_dir = config.html_config().theme.unwrap_or("theme".to_string());
+    let theme = ThemeOverrides::load(&root.join(&theme_dir));
+
+    let start = Instant::now();
+    let css = match &theme.css {
+        Some(css) => Some(css.as_str()),
+        None if config.html_config().default_css.unwrap_or(true) => Some(CSS_FILE),
+        None => None,
+    };
+    let code_theme = config.html_config().code_theme.unwrap_or_default();
+    let css = css.map(|css| with_code_theme(css, &code_theme)).transpose()?;
+    let fonts = config
+        .html_config()
+        .fonts
+        .map(|fonts| fonts::write(root, out, &fonts))
+        .transpose()?;
+    let (font_css, font_preload) = fonts.unzip();
+    let font_preload = font_preload.flatten();
+    let css = match (css, font_css) {
+        (Some(css), Some(font_css)) => Some(format!("{css}\n{font_css}")),
+        (css, _) => css,
+    };
+    let stylesheet = css.map(|css| write_stylesheet(out, &css)).transpose()?;
+
+    let site = SiteContext {
+        footer: config.html_config().footer.as_deref().map(render_footer),
+        additional_head: config
+            .html_config()
+            .additional_head
+            .map(|head| read_additional_head(root, head))
+            .transpose()?,
+        stylesheet,
+        theme,
+        markdown: MarkdownSettings::from_config(config),
+        rewrite: RewriteSettings {
+            lazy_images: config.html_config().lazy_images.unwrap_or(false),
+            image_dimensions: config.html_config().image_dimensions.unwrap_or(false),
+            out_dir: out.to_path_buf(),
+            csp: config.html_config().csp,
+            external_links: config.html_config().external_links.unwrap_or(false),
+            site_host: config.html_config().site_url.as_deref().and_then(link_host),
+        },
+        trailing_slash: config.html_config().trailing_slash,
+        root_language: config.root_language(),
+        reading_progress: config.html_config().reading_progress.unwrap_or(false),
+        lightbox: config.html_config().lightbox.unwrap_or(false),
+        playground_edition: config
+            .html_config()
+            .playground
+            .and_then(|playground| playground.runnable)
+            .unwrap_or(false)
+            .then(|| config.rust.edition.unwrap_or(RustEdition::E2021).as_str().to_string()),
+        offline: config.html_config().offline.unwrap_or(false),
+        analytics: config.html_config().analytics,
+        consent: config.html_config().consent.unwrap_or(false),
+        no_index: config.html_config().robots == Some(Robots::Disallow),
+        dates: DateSettings::from_config(config)?,
+        font_preload,
+    };
+
+    let ssg = Ssg::new(out).with_site(site.clone());
+    let build_config = config.build.clone().unwrap_or_default();
+    let print = config.html_config().print.unwrap_or_default();
+    timings.record("theme", start.elapsed());
+    let start = Instant::now();
+    assets::copy_assets(root, &config.book.src, out, &build_config.force_include)?;
+    timings.record("asset copy", start.elapsed());
+
+    // Checked before building anything, a typo in a target fails fast
+    let chapter_redirects = redirects::from_chapters(book, &site)?;
+
+    let language = LanguageBuild {
+        config,
+        out,
+        site: &site,
+        print: &print,
+        only: only.as_ref(),
+        default_language: default_language.as_deref(),
+    };
+
+    // Languages don't share anything but the read-only settings, each one is built on its own
+    // thread with its own runtime, leptos keeps the state of a render in thread locals
+    let jobs = jobs.unwrap_or_else(default_jobs).max(1);
+    let language = &language;
+    // The chapters of a book without languages, its homepage is the root one
+    let mut home_chapters = None;
+    let search = config.html_config().search.is_some_and(|search| search.enable);
+    // Indexed once preprocessed, like the pages show them
+    let mut indexed = Vec::new();
+    for batch in book.sections.chunks(jobs) {
+        let results = std::thread::scope(|scope| {
+            let handles = batch
+                .iter()
+                .map(|section| scope.spawn(move || language.build_on_own_runtime(section)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(anyhow!("A language build panicked"))))
+                .collect::<Vec<_>>()
+        });
+        for (section, result) in batch.iter().zip(results) {
+            let (lang_chapters, lang_timings) = result?;
+            timings.append(lang_timings);
+            if search {
+                indexed.push((section.language.clone(), lang_chapters.clone()));
+            }
+            if section.language.is_empty() {
+                home_chapters = Some((lang_chapters, section.landing_page));
+            }
+        }
+    }
+
+    if search {
+        let start = Instant::now();
+        search::write_index(config, &indexed, out)?;
+        timings.record("search index", start.elapsed());
+    }
+
+    if only.is_none() {
+        let start = Instant::now();
+        match (&config.book.languages, home_chapters) {
+            // The default language is written to the root, its homepage included
+            (Some(_), _) if site.root_language.is_some() => {}
+            (Some(languages), _) => {
+                let default_language = default_language.as_deref().unwrap_or_default();
+                redirects::write_language_chooser(out, languages, default_language)?;
+            }
+            (None, Some((chapters, landing_page))) if !chapters.is_empty() => {
+                let landing = landing_chapter(&chapters, landing_page).cloned();
+                generate_homepage(&ssg, chapters.into(), landing, String::new()).await?;
+            }
+            (None, _) => {}
+        }
+        generate_standalone_pages(&ssg, config, default_language.unwrap_or_default()).await?;
+        timings.record("html generation", start.elapsed());
+    }
+
+    // Sorted, so the generated files don't change between builds
+    let mut redirect = config.html_config().redirect.unwrap_or_default().redirect.into_iter().collect::<Vec<_>>();
+    redirect.extend(chapter_redirects);
+    redirect.sort();
+    let redirect = || redirect.iter().map(|(from, to)| (from.as_str(), to.as_str()));
+    redirects::write_stubs(out, redirect())?;
+    if let Some(hosting) = config.html_config().hosting {
+        hosting::write_files(out, hosting, redirect())?;
+    }
+    let html_config = config.html_config();
+    robots::write_file(out, html_config.robots.unwrap_or_default(), html_config.site_url.as_deref())?;
+    // Last, the cache of the worker lists every other file
+    if site.offline {
+        let title = config.book.title.clone().unwrap_or_default();
+        offline::write_files(out, &title, &config.default_language().unwrap_or_default())?;
+    }
+
+    Ok(timings)
+}
 
-    let mut chapters = Vec::with_capacity(10);
-    let custom_component = read_to_string("./theme/chapter.html").ok();
+/// Settings shared by the builds of every language.
+struct LanguageBuild<'a> {
+    config: &'a Config,
+    out: &'a Path,
+    site: &'a SiteContext,
+    print: &'a HtmlPrint,
+    only: Option<&'a GlobSet>,
+    default_language: Option<&'a str>,
+}
+
+impl LanguageBuild<'_> {
+    fn build_on_own_runtime(&self, section: &LanguageSection) -> Result<(Vec<Chapter>, Timings)> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .with_context(|| format!("Unable to start the build of language `{}`", section.language))?
+            .block_on(self.build(section))
+    }
 
-    for lang in languages {
-        let chapter_folder = fs::read_dir(format!("./src/{}", lang))?;
-        println!("Reading in {:?}", chapter_folder);
-        println!("--------");
-        chapters.append(&mut charpters_from_folder(chapter_folder)?);
-        println!("{:?}", chapters);
-        println!("--------");
-        println!("GENERACIÓN");
-        println!("--------");
+    /// Preprocesses the chapters of `section` and writes them to the output directory of its
+    /// language, returning them.
+    async fn build(&self, section: &LanguageSection) -> Result<(Vec<Chapter>, Timings)> {
+        let mut timings = Timings::default();
+        let start = Instant::now();
+        let lang = section.language.as_str();
+        // The chapters with `redirect_to` are written as redirects, they aren't pages
+        let mut chapters = section
+            .chapters
+            .iter()
+            .filter(|chapter| chapter.redirect_to.is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        for chapter in &mut chapters {
+            assets::copy_chapter_assets(&self.config.book.src, self.out, chapter)?;
+        }
+        preprocess(self.config, lang, self.default_language, &mut chapters)?;
+        let limit = self.site.markdown.large_code_block;
+        for chapter in &chapters {
+            let largest = chapter.content.as_deref().and_then(|content| largest_code_block(content, limit));
+            if let Some(size) = largest {
+                let mut warning = Diagnostic::warning(format!(
+                    "`{}` has a code block of {size} bytes, over the {limit} of `[output.html.code] large-block-size`, it's collapsed and not highlighted",
+                    chapter.title
+                ));
+                if let Some(path) = &chapter.path {
+                    warning = warning.file(path);
+                }
+                warning.emit();
+            }
+        }
+        for chapter in &mut chapters {
+            let excerpt = chapter
+                .summary
+                .as_deref()
+                .or_else(|| excerpt_markdown(chapter.content.as_deref()?));
+            // Prefixed like on the print page, the listings show several excerpts
+            chapter.excerpt = excerpt
+                .map(|excerpt| render_markdown(excerpt, chapter.slug.as_deref(), &self.site.markdown));
+        }
+        timings.record("preprocessing", start.elapsed());
+        log::debug!("Chapters {:?}", chapters);
 
-        let path = format!("./out/book/{lang}");
+        let out = self.out.join(self.site.language_dir(lang));
+        fs::create_dir_all(&out).with_context(|| format!("Unable to create {}", out.display()))?;
+        let ssg = Ssg::new(&out).with_site(self.site.clone());
 
-        let out = Path::new(&path);
-        if !out.exists() {
-            std::fs::create_dir_all(out).expect("Cannot create 'out' directory");
+        let start = Instant::now();
+        let shared_chapters: Arc<[Chapter]> = chapters.as_slice().into();
+        let custom_component = self.site.theme.chapter.clone();
+        generate_chapters(&ssg, Arc::clone(&shared_chapters), lang.to_string(), custom_component, self.only, &mut timings).await?;
+        // The print page and the homepage aren't chapters, partial builds leave them alone
+        if self.print.enable && self.only.is_none() {
+            let page_break = self.print.page_break.unwrap_or(true);
+            generate_print_page(&ssg, Arc::clone(&shared_chapters), lang.to_string(), page_break).await?;
         }
-        let ssg = Ssg::new(out);
+        // Each language has its own homepage, from its landing page
+        if !lang.is_empty() && self.only.is_none() && !chapters.is_empty() {
+            let landing = landing_chapter(&chapters, section.landing_page).cloned();
+            generate_homepage(&ssg, shared_chapters, landing, lang.to_string()).await?;
+        }
+        // Markdown is rendered while generating the pages
+        timings.record("html generation", start.elapsed());
 
-        _ = generate_chapters(&ssg, chapters.clone(), lang.clone(), custom_component.clone()).await;
+        Ok((chapters, timings))
     }
-    _ = generate_homepage(&ssg, chapters, default_language).await;
+}
 
+/// Runs the preprocessors on the `chapters` of `language`, in order, what both the pages and
+/// the search index are made from.
+fn preprocess(
+    config: &Config,
+    language: &str,
+    default_language: Option<&str>,
+    chapters: &mut [Chapter],
+) -> Result<()> {
+    let content_language = if language.is_empty() {
+        default_language.unwrap_or_default().to_string()
+    } else {
+        language.to_string()
+    };
+    for chapter in chapters.iter_mut() {
+        if let Some(content) = &chapter.content {
+            let content = preprocessors::lang::run(content, &content_language)
+                .with_context(|| format!("Unable to preprocess {:?}", chapter.slug))?;
+            chapter.content = Some(content);
+        }
+    }
+    let allowed_env = config.build.clone().unwrap_or_default().allowed_env;
+    preprocessors::env::run(chapters, &allowed_env)?;
+    preprocessors::transclude::run(chapters, &MarkdownSettings::from_config(config))?;
+    preprocessors::tabs::run(chapters)?;
+    let html_config = config.html_config();
+    let numbered = html_config.number_sections.unwrap_or(false);
+    if numbered && !html_config.no_section_label.unwrap_or(false) {
+        number_chapters(chapters);
+    }
+    // Needs the whole tree, numbered
+    preprocessors::toc::run(chapters, html_config.trailing_slash)?;
     Ok(())
 }
 
+/// Writes the search index of `book` alone, for `build --search-index-only`.
+pub fn write_search_index(book: &Book, out: &Path) -> Result<()> {
+    let default_language = book.config.default_language();
+    let mut sections = Vec::with_capacity(book.sections.len());
+    for section in &book.sections {
+        let mut chapters = section.chapters.clone();
+        preprocess(&book.config, &section.language, default_language.as_deref(), &mut chapters)?;
+        sections.push((section.language.clone(), chapters));
+    }
+    search::write_index(&book.config, &sections, out)
+}
+
+fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |jobs| jobs.get())
+}
+
 async fn generate_chapters<'a>(
     ssg: &Ssg<'a>,
-    chapters: Vec<Chapter>,
+    chapters: Arc<[Chapter]>,
     language: String, 
-    custom_component: Option<String>
-) -> Result<(), Box<dyn std::error::Error>> {
-    
-
-    let chapters_clone = chapters.clone();
-    for chapter in chapters {
-        let path = chapter.slug.clone().unwrap();
-        let path = format!("{path}.html");
+    custom_component: Option<String>,
+    only: Option<&GlobSet>,
+    timings: &mut Timings,
+) -> Result<(), BuildError> {
+    for chapter in chapters.iter() {
+        // Every chapter read from a file gets one, a page without it would have no name
+        let Some(path) = chapter.slug.clone() else {
+            let message = format!("`{}` has no slug, it isn't written", chapter.title);
+            let mut warning = Diagnostic::warning(message);
+            if let Some(file) = &chapter.path {
+                warning = warning.file(file);
+            }
+            warning.emit();
+            continue;
+        };
+        if only.is_some_and(|only| !only.is_match(&path)) {
+            continue;
+        }
+        let path = page_file(&path, ssg.site().trailing_slash);
 
         let chapter_prop = Some(chapter.clone());
-        let chapters_prop = chapters_clone.clone();
+        // Every page shares the same list instead of cloning it
+        let chapters_prop = Arc::clone(&chapters);
         let language_prop = language.clone();
-        
-        if let Some(custom_component) = custom_component.clone() {
-            let mut props = HashMap::<String, String>::new();
-            props.insert("cosa".to_string(), "algooo".to_string());
+        let page = Path::new(&language).join(&path).display().to_string();
+        let start = Instant::now();
 
+        if let Some(custom_component) = custom_component.clone() {
             ssg.gen(path, || CustomComponent(CustomComponentProps{
                 content: custom_component,
-                props
-            })).await?;
+                props: HashMap::new(),
+            })).await.map_err(|error| BuildError::page(&page, error))?;
         }else {
             ssg.gen(path, || Homepage(HomepageProps{
                 chapter:  chapter_prop,
                 chapters: chapters_prop,
-                language: language_prop
-            })).await?;
+                language: language_prop,
+                is_home: false,
+            })).await.map_err(|error| BuildError::page(&page, error))?;
         }
+        timings.record_page(page, start.elapsed());
     }
 
     Ok(())
 }
 
+async fn generate_print_page<'a>(
+    ssg: &Ssg<'a>,
+    chapters: Arc<[Chapter]>,
+    language: String,
+    page_break: bool,
+) -> Result<(), BuildError> {
+    let page = Path::new(&language).join("print.html").display().to_string();
+    ssg.gen("print.html".to_owned(), move || {
+        PrintPage(PrintPageProps {
+            chapters,
+            language,
+            page_break,
+        })
+    })
+    .await
+    .map_err(|error| BuildError::page(&page, error))?;
+
+    Ok(())
+}
+
 async fn generate_homepage<'a>(
     ssg: &Ssg<'a>,
-    chapters: Vec<Chapter>,
-    default_language: Option<String>
-) -> Result<(), Box<dyn std::error::Error>> {
+    chapters: Arc<[Chapter]>,
+    landing: Option<Chapter>,
+    language: String,
+) -> Result<(), BuildError> {
+    let page = Path::new(&language).join("index.html").display().to_string();
     ssg.gen("index.html".to_owned(), || {
         Homepage(HomepageProps {
             chapters,
-            chapter: None,
-            language: default_language.unwrap_or("".to_string())
+            chapter: landing,
+            language,
+            is_home: true,
         })
     })
-    .await?;
+    .await
+    .map_err(|error| BuildError::page(&page, error))?;
+
+    Ok(())
+}
+
+/// Writes `404.html` from the not-found markdown of the book, or the built-in one, and
+/// `offline.html` when the book has an `offline.md`.
+async fn generate_standalone_pages<'a>(ssg: &Ssg<'a>, config: &Config, language: String) -> Result<()> {
+    let not_found_file = config.html_config().input_404.unwrap_or(NOT_FOUND_FILE.to_string());
+    let not_found = read_page(&config.book.src.join(not_found_file))?.unwrap_or(NOT_FOUND.to_string());
+    let offline = read_page(&config.book.src.join(OFFLINE_FILE))?;
+
+    for (file, content) in [("404.html", Some(not_found)), ("offline.html", offline)] {
+        let Some(content) = content else { continue };
+        let language = language.clone();
+        ssg.gen(file.to_owned(), move || StandalonePage(StandalonePageProps { content, language }))
+            .await
+            .map_err(|error| BuildError::page(file, error))?;
+    }
 
     Ok(())
 }
 
-fn charpters_from_folder(chapter_folder: ReadDir) -> Result<Vec<Chapter>> {
-    let mut chapters = Vec::with_capacity(10);
+/// Markdown of an optional page, `None` when the file doesn't exist.
+fn read_page(path: &Path) -> Result<Option<String>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Unable to read {}", path.display()))
+}
+
+/// Renders the `[output.html] footer` markdown, replacing the `{year}` placeholder.
+fn render_footer(footer: &str) -> String {
+    let footer = footer.replace("{year}", &chrono::Utc::now().year().to_string());
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new(&footer));
+    html
+}
+
+/// Appends the colors of the highlighted code to `css`, the ones of the dark theme scoped to the
+/// class the theme switch toggles.
+fn with_code_theme(css: &str, code_theme: &CodeTheme) -> Result<String> {
+    let light = code_theme.light.as_deref().unwrap_or(highlight::DEFAULT_LIGHT_THEME);
+    let dark = code_theme.dark.as_deref().unwrap_or(highlight::DEFAULT_DARK_THEME);
+    let colors = highlight::theme_css(light, dark).context("Invalid [output.html.code-theme]")?;
+    Ok(format!("{css}\n{colors}"))
+}
+
+/// Writes `css` as `style.<hash>.css`, removing the stylesheets of previous builds, and returns
+/// its URL. The name changes with the contents, so it can be cached forever.
+fn write_stylesheet(out: &Path, css: &str) -> Result<String> {
+    let name = stylesheet_name(css);
+
+    let entries = fs::read_dir(out).with_context(|| format!("Unable to read {}", out.display()))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file = entry.file_name();
+        let Some(file) = file.to_str() else { continue };
+        if file != name && is_stylesheet_name(file) {
+            fs::remove_file(entry.path())
+                .with_context(|| format!("Unable to remove {}", entry.path().display()))?;
+        }
+    }
+
+    write_if_changed(&out.join(&name), css)?;
+    Ok(format!("/{name}"))
+}
+
+/// Hex digits of the hash in the name of the stylesheet.
+const STYLESHEET_HASH_LEN: usize = 16;
+
+/// File name of the stylesheet `css`, with the hash of its contents. The hash is stable across
+/// Rust versions, so upgrading doesn't invalidate the cached stylesheet.
+fn stylesheet_name(css: &str) -> String {
+    let hash = Sha256::digest(css.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("style.{}.css", &hash[..STYLESHEET_HASH_LEN])
+}
+
+/// Whether `file` is named like the stylesheets [`stylesheet_name`] writes. The other
+/// `style.*.css` files of the output, like the ones copied from `src`, are the book's own.
+fn is_stylesheet_name(file: &str) -> bool {
+    file.strip_prefix("style.")
+        .and_then(|file| file.strip_suffix(".css"))
+        .is_some_and(|hash| {
+            hash.len() == STYLESHEET_HASH_LEN
+                && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        })
+}
+
+/// The stylesheet bundled with mdbook-killer, after a comment with the version that ships it
+/// and the name it's written as. Theme overrides can be diffed against it after an upgrade.
+pub fn bundled_css() -> String {
+    format!(
+        "/* mdbook-killer {} bundled theme, written as {} */\n{CSS_FILE}",
+        env!("CARGO_PKG_VERSION"),
+        stylesheet_name(CSS_FILE)
+    )
+}
+
+/// Writes `contents` unless `path` already has them, so rebuilds keep the modification time of
+/// the stylesheet and the tools watching the output don't reload it for nothing.
+fn write_if_changed(path: &Path, contents: &str) -> Result<()> {
+    if fs::read(path).is_ok_and(|current| current == contents.as_bytes()) {
+        return Ok(());
+    }
+    fs::write(path, contents).with_context(|| format!("Unable to write {}", path.display()))
+}
+
+/// Matcher of the `--only` globs, `None` when the whole book is built.
+fn chapter_filter(globs: &[String]) -> Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).with_context(|| format!("Invalid --only glob `{glob}`"))?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// `[output.html] additional-head` is either markup or a file with it.
+fn read_additional_head(root: &Path, head: String) -> Result<String> {
+    let path = root.join(&head);
+    if head.trim_start().starts_with('<') || !path.is_file() {
+        return Ok(head);
+    }
+    fs::read_to_string(&path).with_context(|| format!("Unable to read {}", path.display()))
+}
+
+/// Numbers the chapters by their position in the tree of the navigation, `1.`, `1.1.`, `1.2.`,
+/// `2.`... Hidden chapters aren't numbered, a chapter is numbered under its nearest visible
+/// ancestor, so the children of a hidden one and the chapters skipping a level don't get zeros.
+fn number_chapters(chapters: &mut [Chapter]) {
+    let mut counters: Vec<u32> = Vec::new();
+    // The depth of the ancestors of the current chapter and whether they're visible
+    let mut ancestors: Vec<(usize, bool)> = Vec::new();
+    for chapter in chapters.iter_mut() {
+        while ancestors.last().is_some_and(|&(depth, _)| depth >= chapter.depth) {
+            ancestors.pop();
+        }
+        let level = ancestors.iter().filter(|&&(_, visible)| visible).count();
+        ancestors.push((chapter.depth, chapter.in_toc()));
+        if !chapter.in_toc() {
+            continue;
+        }
+
+        counters.truncate(level + 1);
+        counters.resize(level + 1, 0);
+        counters[level] += 1;
+        chapter.number = Some(SectionNumber(counters.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(depth: usize, toc: bool) -> Chapter {
+        Chapter {
+            depth,
+            toc: Some(toc),
+            ..Default::default()
+        }
+    }
 
-    for path in chapter_folder {
-        let file = path?.path();
-        let algo = fs::read_to_string(file.clone())?;
-        let file = file
-            .file_stem()
+    #[test]
+    fn only_the_previous_stylesheets_are_removed() {
+        let out = tempfile::tempdir().unwrap();
+        let previous = stylesheet_name("body {}");
+        fs::write(out.path().join(&previous), "body {}").unwrap();
+        fs::write(out.path().join("style.print.css"), "").unwrap();
+
+        let url = write_stylesheet(out.path(), "main {}").unwrap();
+
+        assert_eq!(url, format!("/{}", stylesheet_name("main {}")));
+        assert!(out.path().join(&url[1..]).exists());
+        assert!(!out.path().join(previous).exists());
+        assert!(out.path().join("style.print.css").exists());
+        assert!(!is_stylesheet_name("style.0123456789ABCDEF.css"));
+    }
+
+    #[test]
+    fn the_search_index_has_the_preprocessed_chapters() {
+        let out = tempfile::tempdir().unwrap();
+        let config = "[rust]\n[book]\nlanguages = [\"es\"]\n[language.es]\n";
+        let content = "Hola{{#lang en}} hello{{/lang}}{{#lang es}} mundo{{/lang}}";
+        let book = Book {
+            config: toml::from_str(config).unwrap(),
+            root: PathBuf::from("."),
+            sections: vec![LanguageSection {
+                language: "es".to_string(),
+                chapters: vec![Chapter {
+                    title: "Intro".to_string(),
+                    slug: Some("intro".to_string()),
+                    content: Some(content.to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        write_search_index(&book, out.path()).unwrap();
+
+        let index = fs::read_to_string(out.path().join(search::INDEX_FILE)).unwrap();
+        assert!(index.contains("Hola mundo"));
+        assert!(!index.contains("hello") && !index.contains("{{#lang"));
+    }
+
+    #[tokio::test]
+    async fn chapters_without_a_slug_are_skipped() {
+        let out = tempfile::tempdir().unwrap();
+        let ssg = Ssg::new(out.path()).with_site(SiteContext::default());
+        let chapters: Arc<[Chapter]> = vec![
+            Chapter {
+                title: "Untitled".to_string(),
+                ..Default::default()
+            },
+            Chapter {
+                title: "Intro".to_string(),
+                slug: Some("intro".to_string()),
+                ..Default::default()
+            },
+        ]
+        .into();
+        let template = Some("<p>Page</p>".to_string());
+
+        generate_chapters(&ssg, chapters, String::new(), template, None, &mut Timings::default())
+            .await
+            .unwrap();
+
+        let written = fs::read_dir(out.path())
             .unwrap()
-            .to_str()
-            .with_context(|| "Could not convert path to str")?;
-        if algo.starts_with("---") {
-            let matter = Matter::<YAML>::new();
-            let result = matter.parse_with_struct::<Chapter>(&algo);
-            let Some(parsed_entity) = result else {
-                println!("Error parsing file: {file:?}");
-                continue;
-            };
-            let mut chapter: Chapter = parsed_entity.data;
-            chapter.content = Some(parsed_entity.content);
-
-            chapter.slug.get_or_insert(file.to_string());
-
-            chapters.push(chapter);
-        } else {
-            let title = algo.clone();
-            let title = title
-                .lines()
-                .next()
-                .ok_or(anyhow!("No se pudo obtener un titulo"))?;
-
-            let chapter = Chapter {
-                title: title.to_string(),
-                content: Some(algo),
-                slug: Some(file.to_string()),
-            };
-            chapters.push(chapter);
-        }
-    }
-
-    Ok(chapters)
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(written, ["intro.html"]);
+    }
+
+    #[test]
+    fn section_numbers_follow_the_tree() {
+        let mut chapters = [
+            chapter(0, true),
+            chapter(1, true),
+            chapter(1, false),
+            chapter(1, true),
+            chapter(2, true),
+            chapter(0, true),
+        ];
+        number_chapters(&mut chapters);
+
+        let numbers = chapters
+            .iter()
+            .map(|chapter| chapter.number.as_ref().map(ToString::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            numbers,
+            [
+                Some("1.".to_string()),
+                Some("1.1.".to_string()),
+                None,
+                Some("1.2.".to_string()),
+                Some("1.2.1.".to_string()),
+                Some("2.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn section_numbers_skip_the_hidden_and_missing_levels() {
+        let mut chapters = [
+            chapter(0, true),
+            chapter(0, false),
+            chapter(1, true),
+            chapter(1, true),
+            chapter(0, true),
+            chapter(2, true),
+            chapter(1, true),
+        ];
+        number_chapters(&mut chapters);
+
+        let numbers = chapters
+            .iter()
+            .map(|chapter| chapter.number.as_ref().map(ToString::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            numbers,
+            [
+                Some("1.".to_string()),
+                None,
+                // The children of the hidden chapter take its place
+                Some("2.".to_string()),
+                Some("3.".to_string()),
+                Some("4.".to_string()),
+                Some("4.1.".to_string()),
+                Some("4.2.".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,193 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+
+use crate::diagnostic::Diagnostic;
+
+use crate::models::book::within_root;
+use crate::models::Chapter;
+
+/// Copies every file of `src` that isn't a chapter into `out`, keeping the directory structure.
+///
+/// Files ignored by the `.gitignore` of the book are skipped, unless they're listed in
+/// `force_include` (relative to `src`). Symlinks are followed as long as they stay inside
+/// `root`, the root directory of the book.
+pub fn copy_assets(root: &Path, src: &Path, out: &Path, force_include: &[PathBuf]) -> Result<()> {
+    let src = src
+        .canonicalize()
+        .with_context(|| format!("Unable to find the source directory {}", src.display()))?;
+
+    // The output is inside the sources for a book without `book.toml`, it isn't an asset
+    let out_dir = out.canonicalize().ok();
+    let walker = WalkBuilder::new(&src)
+        .standard_filters(false)
+        .hidden(true)
+        .parents(true)
+        .git_ignore(true)
+        .require_git(false)
+        .follow_links(true)
+        .filter_entry(move |entry| !out_dir.as_ref().is_some_and(|out| entry.path().starts_with(out)))
+        .build();
+    let forced = force_include.iter().map(|path| {
+        WalkBuilder::new(src.join(path))
+            .standard_filters(false)
+            .follow_links(true)
+            .build()
+    });
+
+    for entry in std::iter::once(walker).chain(forced).flatten() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            // Symlink cycles are reported here instead of being walked forever
+            Err(error) if is_loop(&error) => {
+                Diagnostic::warning(format!("skipping symlink cycle: {error}")).emit();
+                continue;
+            }
+            Err(error) => Err(error)?,
+        };
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_some_and(|ext| ext == "md") {
+            continue;
+        }
+        if !within_root(path, root) {
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(&src)
+            .with_context(|| format!("{} is outside of the source directory", path.display()))?;
+        let target = out.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(path, &target)
+            .with_context(|| format!("Unable to copy {} to {}", path.display(), target.display()))?;
+        log::debug!("Copied asset {}", relative.display());
+    }
+
+    Ok(())
+}
+
+/// Copies the `css` and `scripts` of `chapter` into `out`, where [`copy_assets`] puts the files
+/// of `src`, and links its page to them. A missing file or one outside of `src` is an error.
+pub fn copy_chapter_assets(src: &Path, out: &Path, chapter: &mut Chapter) -> Result<()> {
+    // The files are canonicalized to find them, the source directory must be too to compare
+    let src = &src
+        .canonicalize()
+        .with_context(|| format!("Unable to find the source directory {}", src.display()))?;
+    let folder = chapter
+        .path
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(src)
+        .to_path_buf();
+    let css = chapter.css.clone().unwrap_or_default();
+    let scripts = chapter.scripts.clone().unwrap_or_default();
+
+    for (file, kind) in css.iter().map(|file| (file, "stylesheet")) {
+        let link = copy_chapter_asset(src, out, &folder.join(file), kind, &chapter.title)?;
+        chapter.css_links.push(link);
+    }
+    for (file, kind) in scripts.iter().map(|file| (file, "script")) {
+        let link = copy_chapter_asset(src, out, &folder.join(file), kind, &chapter.title)?;
+        chapter.script_links.push(link);
+    }
+
+    Ok(())
+}
+
+/// Copies `file`, a `kind` of the chapter titled `title`, returning its absolute link.
+fn copy_chapter_asset(
+    src: &Path,
+    out: &Path,
+    file: &Path,
+    kind: &str,
+    title: &str,
+) -> Result<String> {
+    let canonical = file.canonicalize().with_context(|| {
+        format!("Unable to find {}, a {kind} of the chapter `{title}`", file.display())
+    })?;
+    let relative = canonical.strip_prefix(src).with_context(|| {
+        let file = file.display();
+        format!("{file}, a {kind} of the chapter `{title}`, is outside of the source directory")
+    })?;
+
+    let target = out.join(relative);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(&canonical, &target)
+        .with_context(|| format!("Unable to copy {} to {}", file.display(), target.display()))?;
+
+    let link = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Ok(format!("/{link}"))
+}
+
+/// Whether the walker stopped because of a symlink cycle, the error may carry its location.
+fn is_loop(error: &ignore::Error) -> bool {
+    match error {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_loop(err),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(src: &Path, css: &[&str], scripts: &[&str]) -> Chapter {
+        Chapter {
+            title: "Demo".to_string(),
+            path: Some(src.join("guide").join("demo.md")),
+            css: Some(css.iter().map(PathBuf::from).collect()),
+            scripts: Some(scripts.iter().map(PathBuf::from).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chapter_assets_are_copied_next_to_the_other_files_of_src() {
+        let dir = tempfile::tempdir().unwrap();
+        let (src, out) = (dir.path().join("src"), dir.path().join("out"));
+        fs::create_dir_all(src.join("guide")).unwrap();
+        fs::create_dir_all(src.join("shared")).unwrap();
+        fs::write(src.join("guide/demo.css"), "p {}").unwrap();
+        fs::write(src.join("shared/demo.js"), "run()").unwrap();
+
+        // A relative `src` works as well as the canonical one
+        let relative_src = src.join("guide").join("..");
+        let mut chapter = chapter(&src, &["demo.css"], &["../shared/demo.js"]);
+        copy_chapter_assets(&relative_src, &out, &mut chapter).unwrap();
+
+        assert_eq!(chapter.css_links, ["/guide/demo.css"]);
+        assert_eq!(chapter.script_links, ["/shared/demo.js"]);
+        assert_eq!(fs::read_to_string(out.join("guide/demo.css")).unwrap(), "p {}");
+        assert_eq!(fs::read_to_string(out.join("shared/demo.js")).unwrap(), "run()");
+    }
+
+    #[test]
+    fn missing_chapter_assets_and_ones_outside_src_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let (src, out) = (dir.path().join("src"), dir.path().join("out"));
+        fs::create_dir_all(src.join("guide")).unwrap();
+        fs::write(dir.path().join("outside.css"), "p {}").unwrap();
+
+        let mut missing = chapter(&src, &["missing.css"], &[]);
+        let error = copy_chapter_assets(&src, &out, &mut missing).unwrap_err();
+        assert!(error.to_string().contains("a stylesheet of the chapter `Demo`"));
+
+        let mut outside = chapter(&src, &["../../outside.css"], &[]);
+        let error = copy_chapter_assets(&src, &out, &mut outside).unwrap_err();
+        assert!(error.to_string().ends_with("is outside of the source directory"));
+        assert!(!out.join("outside.css").exists());
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Directory an atomic build of `out` is written to, next to it so renaming it over `out`
+/// usually stays on the same filesystem.
+pub fn staging_dir(out: &Path) -> PathBuf {
+    sibling(out, "tmp")
+}
+
+/// Replaces `out` with the finished build in `staging`. The previous output is moved aside
+/// first and only removed once `staging` took its place, it's restored when that fails.
+pub fn replace(staging: &Path, out: &Path) -> Result<()> {
+    let previous = sibling(out, "old");
+    remove_dir(&previous)?;
+    if out.exists() {
+        fs::rename(out, &previous)
+            .with_context(|| format!("Unable to move the previous output {} aside", out.display()))?;
+    }
+
+    if let Err(error) = move_dir(staging, out) {
+        if previous.exists() {
+            _ = remove_dir(out);
+            _ = fs::rename(&previous, out);
+        }
+        return Err(error);
+    }
+
+    remove_dir(&previous)
+}
+
+/// Removes the directory `path`, if there's one.
+pub fn remove_dir(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_dir_all(path).with_context(|| format!("Unable to remove {}", path.display()))
+}
+
+/// `.<name>.<suffix>` next to `out`.
+fn sibling(out: &Path, suffix: &str) -> PathBuf {
+    let name = out.file_name().and_then(|name| name.to_str()).unwrap_or("book");
+    out.with_file_name(format!(".{name}.{suffix}"))
+}
+
+/// Renames `from` to `to`, copying it when they're on different filesystems.
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    log::debug!("Unable to rename {} to {}, copying it", from.display(), to.display());
+    copy_dir(from, to)?;
+    remove_dir(from)
+}
+
+/// Copies the directory `from` and everything inside it to `to`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).with_context(|| format!("Unable to create {}", to.display()))?;
+    let entries = fs::read_dir(from).with_context(|| format!("Unable to read {}", from.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target).with_context(|| {
+                format!("Unable to copy {} to {}", entry.path().display(), target.display())
+            })?;
+        }
+    }
+
+    Ok(())
+}
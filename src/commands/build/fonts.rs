@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Component, Path};
+
+use anyhow::{bail, Context, Result};
+
+use crate::models::preprocessors_config::{FontFace, Fonts};
+
+/// Directory of the output the font files are copied to.
+const FONTS_DIR: &str = "fonts";
+
+/// Fonts of the system, after the families of `[output.html.fonts]`.
+const SANS_FALLBACK: &str = r#"ui-sans-serif, system-ui, sans-serif, "Apple Color Emoji", "Segoe UI Emoji", "Segoe UI Symbol", "Noto Color Emoji""#;
+const MONO_FALLBACK: &str = r#"ui-monospace, SFMono-Regular, Menlo, Monaco, Consolas, "Liberation Mono", "Courier New", monospace"#;
+
+/// Copies the font files of `fonts` to `out`, at their path relative to `root` so the faces of
+/// different folders with the same file name don't overwrite each other, and returns the CSS
+/// declaring and using them, appended to the stylesheet, with the URL of the font to preload: the
+/// first face of the body family.
+pub fn write(root: &Path, out: &Path, fonts: &Fonts) -> Result<(String, Option<String>)> {
+    let dir = out.join(FONTS_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("Unable to create {}", dir.display()))?;
+
+    let mut css = String::new();
+    let mut preload = None;
+    for face in &fonts.faces {
+        let relative = relative_path(&face.src)?;
+        let source = root.join(&face.src);
+        let target = dir.join(relative.join("/"));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Unable to create {}", parent.display()))?;
+        }
+        fs::copy(&source, &target)
+            .with_context(|| format!("Unable to copy the font {}", source.display()))?;
+
+        let url = format!("/{FONTS_DIR}/{}", relative.join("/"));
+        css.push_str(&font_face(face, &url));
+        if preload.is_none() && fonts.body.as_deref() == Some(face.family.as_str()) {
+            preload = Some(url);
+        }
+    }
+
+    if let Some(body) = &fonts.body {
+        css.push_str(&format!("body {{ font-family: {}, {SANS_FALLBACK}; }}\n", family(body)));
+    }
+    if let Some(code) = &fonts.code {
+        css.push_str(&format!(
+            "code, kbd, samp, pre {{ font-family: {}, {MONO_FALLBACK}; }}\n",
+            family(code)
+        ));
+    }
+
+    Ok((css, preload))
+}
+
+/// Components of the path `src` of a font, which must be a file inside the book root.
+fn relative_path(src: &str) -> Result<Vec<&str>> {
+    let mut components = Vec::new();
+    for component in Path::new(src).components() {
+        match component {
+            Component::Normal(name) => components.push(
+                name.to_str().with_context(|| format!("The font `{src}` isn't a valid path"))?,
+            ),
+            Component::CurDir => {}
+            _ => bail!("The font `{src}` isn't a file of the book root"),
+        }
+    }
+    if components.is_empty() {
+        bail!("The font `{src}` isn't a file");
+    }
+    Ok(components)
+}
+
+fn font_face(face: &FontFace, url: &str) -> String {
+    let format = match extension(url) {
+        "woff" => "woff",
+        "ttf" => "truetype",
+        "otf" => "opentype",
+        _ => "woff2",
+    };
+    let mut css = format!(
+        "@font-face {{ font-family: {}; src: url(\"{url}\") format(\"{format}\"); font-display: swap;",
+        family(&face.family)
+    );
+    if let Some(weight) = &face.weight {
+        css.push_str(&format!(" font-weight: {weight};"));
+    }
+    if let Some(style) = &face.style {
+        css.push_str(&format!(" font-style: {style};"));
+    }
+    css.push_str(" }\n");
+    css
+}
+
+/// `family` quoted for CSS.
+fn family(family: &str) -> String {
+    format!("\"{}\"", family.replace(['"', '\\'], ""))
+}
+
+fn extension(url: &str) -> &str {
+    url.rsplit_once('.').map_or("", |(_, extension)| extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(src: &str) -> FontFace {
+        FontFace {
+            family: "Inter".to_string(),
+            src: src.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fonts_keep_their_path_relative_to_the_root() {
+        let root = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        for folder in ["a", "b"] {
+            fs::create_dir(root.path().join(folder)).unwrap();
+            fs::write(root.path().join(folder).join("Regular.woff2"), folder).unwrap();
+        }
+        let fonts = Fonts {
+            body: Some("Inter".to_string()),
+            faces: vec![face("a/Regular.woff2"), face("./b/Regular.woff2")],
+            ..Default::default()
+        };
+
+        let (css, preload) = write(root.path(), out.path(), &fonts).unwrap();
+
+        assert_eq!(preload.as_deref(), Some("/fonts/a/Regular.woff2"));
+        assert!(css.contains(r#"url("/fonts/b/Regular.woff2") format("woff2")"#));
+        let copied = |file| fs::read_to_string(out.path().join("fonts").join(file)).unwrap();
+        assert_eq!(copied("a/Regular.woff2"), "a");
+        assert_eq!(copied("b/Regular.woff2"), "b");
+    }
+
+    #[test]
+    fn fonts_outside_the_root_are_errors() {
+        assert!(relative_path("../Regular.woff2").is_err());
+        assert!(relative_path("/usr/share/fonts/Regular.woff2").is_err());
+        assert!(relative_path(".").is_err());
+        assert_eq!(relative_path("./fonts/Regular.ttf").unwrap(), ["fonts", "Regular.ttf"]);
+    }
+}
@@ -0,0 +1,45 @@
+//! Commands of `[build] pre-build` and `post-build`, run by the shell in the book root.
+//!
+//! They get these environment variables:
+//! - `MDBOOK_KILLER_ROOT`, the root directory of the book;
+//! - `MDBOOK_KILLER_OUTPUT_DIR`, the directory the book is written to;
+//! - `MDBOOK_KILLER_HOOK`, `pre-build` or `post-build`.
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Runs the `commands` of the `hook` one after the other, the first failing one is an error and
+/// the next ones don't run.
+pub fn run(hook: &str, commands: &[String], root: &Path, out: &Path) -> Result<()> {
+    for command in commands {
+        log::info!("Running the {hook} command `{command}`");
+        let status = shell(command)
+            .current_dir(root)
+            .env("MDBOOK_KILLER_ROOT", root)
+            .env("MDBOOK_KILLER_OUTPUT_DIR", out)
+            .env("MDBOOK_KILLER_HOOK", hook)
+            .status()
+            .with_context(|| format!("Unable to run the {hook} command `{command}`"))?;
+        if !status.success() {
+            bail!("The {hook} command `{command}` failed with {status}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
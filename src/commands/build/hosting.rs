@@ -0,0 +1,45 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::models::preprocessors_config::Hosting;
+
+use super::redirects::absolute_target;
+
+/// The pages keep their URLs between builds, browsers must check for a newer version.
+/// The stylesheet has the hash of its contents in its name, so it never changes. The service worker of
+/// `[output.html] offline` is always checked, its cache name changes with every build.
+const HEADERS: &str = "/*.html
+  Cache-Control: public, max-age=0, must-revalidate
+/
+  Cache-Control: public, max-age=0, must-revalidate
+/style.*.css
+  Cache-Control: public, max-age=31536000, immutable
+/sw.js
+  Cache-Control: public, max-age=0, must-revalidate
+";
+
+/// Writes the configuration files of `hosting` to `out`.
+pub fn write_files<'a>(
+    out: &Path,
+    hosting: Hosting,
+    redirects: impl Iterator<Item = (&'a str, &'a str)>,
+) -> Result<()> {
+    match hosting {
+        Hosting::Netlify | Hosting::Cloudflare => {
+            let mut rules = String::new();
+            for (from, to) in redirects {
+                let from = format!("/{}", from.trim_start_matches('/'));
+                writeln!(rules, "{from} {} 301", absolute_target(&from, to))?;
+            }
+
+            fs::write(out.join("_headers"), HEADERS).with_context(|| "Unable to write _headers")?;
+            fs::write(out.join("_redirects"), rules)
+                .with_context(|| "Unable to write _redirects")?;
+        }
+    }
+
+    Ok(())
+}
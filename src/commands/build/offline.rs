@@ -0,0 +1,107 @@
+//! Service worker caching the book for offline reading, see `[output.html] offline`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use serde_json::json;
+
+pub const WORKER_FILE: &str = "sw.js";
+pub const MANIFEST_FILE: &str = "manifest.webmanifest";
+
+/// Extensions of the files cached when the worker is installed.
+const CACHED: [&str; 4] = ["html", "css", "js", "json"];
+
+/// Serves the cached files, falling back to the network. Pages are also looked up with the
+/// `index.html` and `.html` suffixes the `trailing-slash` styles leave out of the URLs, and the
+/// offline page, when there's one, replaces the pages missing from the cache without network.
+const WORKER: &str = r#"
+self.addEventListener('install', (event) => {
+    event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(FILES)).then(() => self.skipWaiting()));
+});
+
+self.addEventListener('activate', (event) => {
+    event.waitUntil(
+        caches.keys()
+            .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+async function cached(request) {
+    const url = new URL(request.url);
+    url.search = '';
+    url.hash = '';
+    const candidates = [url.href];
+    if (request.mode === 'navigate') {
+        candidates.push(url.href.endsWith('/') ? url.href + 'index.html' : url.href + '.html');
+    }
+    const cache = await caches.open(CACHE);
+    for (const candidate of candidates) {
+        const response = await cache.match(candidate);
+        if (response) return response;
+    }
+    return undefined;
+}
+
+self.addEventListener('fetch', (event) => {
+    const request = event.request;
+    if (request.method !== 'GET' || new URL(request.url).origin !== self.location.origin) return;
+
+    event.respondWith(
+        cached(request).then((response) => response || fetch(request).catch(async () => {
+            const offline = request.mode === 'navigate' && await caches.match('/offline.html');
+            return offline || Response.error();
+        }))
+    );
+});
+"#;
+
+/// Writes the service worker and the web app manifest, after every other file of `out`.
+/// The name of the cache is a hash of the cached files, a build changing any of them replaces
+/// the cache of the previous one.
+pub fn write_files(out: &Path, name: &str, language: &str) -> Result<()> {
+    let mut files = WalkBuilder::new(out)
+        .standard_filters(false)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.extension().is_some_and(|ext| CACHED.iter().any(|cached| ext == *cached)))
+        .filter(|path| !path.ends_with(WORKER_FILE))
+        .collect::<Vec<_>>();
+    // Sorted, so the same files always give the same hash
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    let mut urls = vec!["/".to_string()];
+    for file in &files {
+        let contents = fs::read(file).with_context(|| format!("Unable to read {}", file.display()))?;
+        let relative = file.strip_prefix(out).unwrap_or(file);
+        let url = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+        url.hash(&mut hasher);
+        contents.hash(&mut hasher);
+        urls.push(url);
+    }
+
+    let worker = format!(
+        "const CACHE = 'book-{:016x}';\nconst FILES = {};\n{WORKER}",
+        hasher.finish(),
+        serde_json::to_string(&urls)?
+    );
+    fs::write(out.join(WORKER_FILE), worker).with_context(|| format!("Unable to write {WORKER_FILE}"))?;
+
+    let manifest = json!({
+        "name": name,
+        "short_name": name,
+        "lang": language,
+        "start_url": "/",
+        "scope": "/",
+        "display": "standalone",
+    });
+    fs::write(out.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Unable to write {MANIFEST_FILE}"))
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path};
+
+use anyhow::{bail, Context, Result};
+
+use crate::default_theme::links::page_file;
+use crate::models::book::Book;
+use crate::renderer::markdown::escape_attr;
+use crate::renderer::ssg::SiteContext;
+
+/// Writes a page at every old location of `[output.html.redirect]` sending the readers to
+/// the new one. Fails when an old location isn't a file inside `out`, like `../index.html`.
+pub fn write_stubs<'a>(out: &Path, redirects: impl Iterator<Item = (&'a str, &'a str)>) -> Result<()> {
+    for (from, to) in redirects {
+        let path = Path::new(from.trim_start_matches('/'));
+        let inside = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside || path.as_os_str().is_empty() {
+            bail!("The redirect `{from}` of [output.html.redirect] isn't a page of the output");
+        }
+        let file = out.join(path);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let to = escape_attr(to);
+        let html = format!(
+            r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>Redirecting...</title><meta http-equiv="refresh" content="0; URL={to}"><link rel="canonical" href="{to}"></head><body><p>Redirecting to <a href="{to}">{to}</a>...</p></body></html>"#
+        );
+        fs::write(&file, html)
+            .with_context(|| format!("Unable to write the redirect {}", file.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the root `index.html` of a book with `languages`, sending the readers to the homepage
+/// of the first language their browser prefers, by its full tag or its primary subtag, or else
+/// to the one of `default_language`. Static hosts don't see `Accept-Language`, the browser
+/// exposes the same list as `navigator.languages`.
+pub fn write_language_chooser(out: &Path, languages: &[String], default_language: &str) -> Result<()> {
+    let default = escape_attr(&format!("/{default_language}/"));
+    let links = languages
+        .iter()
+        .map(|language| {
+            let language = escape_attr(language);
+            format!(r#"<li><a href="/{language}/" hreflang="{language}">{language}</a></li>"#)
+        })
+        .collect::<String>();
+    let available = serde_json::to_string(languages)?.replace('<', "\\u003c");
+    let fallback = serde_json::to_string(default_language)?.replace('<', "\\u003c");
+    let html = format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>Redirecting...</title><script>
+const available = {available};
+const preferred = navigator.languages || [navigator.language || ''];
+let target = {fallback};
+for (const tag of preferred.map((tag) => tag.toLowerCase())) {{
+    const match = available.find((lang) => lang.toLowerCase() === tag)
+        || available.find((lang) => lang.toLowerCase() === tag.split('-')[0]);
+    if (match) {{ target = match; break; }}
+}}
+location.replace('/' + target + '/');
+</script><meta http-equiv="refresh" content="0; URL={default}"></head><body><ul>{links}</ul></body></html>"#
+    );
+    fs::write(out.join("index.html"), html).with_context(|| "Unable to write index.html")
+}
+
+/// Absolute location of the `to` redirect of the page `from`, for the hosts that only accept
+/// absolute paths.
+pub fn absolute_target(from: &str, to: &str) -> String {
+    if to.starts_with('/') || to.contains("://") {
+        return to.to_string();
+    }
+
+    let mut segments = from.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    // The old page itself
+    segments.pop();
+    for segment in to.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// The redirects declared with `redirect_to` in the frontmatter of the chapters, from the file
+/// of the chapter to its target. Fails when a target isn't an absolute URL nor a page of the book.
+pub fn from_chapters(book: &Book, site: &SiteContext) -> Result<Vec<(String, String)>> {
+    let mut pages = HashSet::new();
+    for section in &book.sections {
+        let dir = site.language_dir(&section.language);
+        // The homepage of the language
+        pages.insert(page_key(dir));
+        for chapter in section.chapters.iter().filter(|chapter| chapter.redirect_to.is_none()) {
+            if let Some(slug) = &chapter.slug {
+                pages.insert(page_key(&format!("{dir}/{slug}")));
+            }
+        }
+    }
+
+    let mut redirects = Vec::new();
+    for section in &book.sections {
+        let dir = site.language_dir(&section.language);
+        for chapter in &section.chapters {
+            let (Some(to), Some(slug)) = (&chapter.redirect_to, &chapter.slug) else {
+                continue;
+            };
+            let file = format!("{dir}/{}", page_file(slug, site.trailing_slash));
+            let from = format!("/{}", file.trim_start_matches('/'));
+            if !to.contains("://") && !pages.contains(&page_key(&absolute_target(&from, to))) {
+                let file = chapter.path.as_deref().unwrap_or(Path::new(slug));
+                bail!("`redirect_to: {to}` of {} isn't a page of the book", file.display());
+            }
+            redirects.push((from, to.clone()));
+        }
+    }
+
+    Ok(redirects)
+}
+
+/// The page a link points to, without its fragment, query, extension or surrounding slashes,
+/// so the URL styles of `[output.html] trailing-slash` compare equal.
+fn page_key(link: &str) -> String {
+    let link = link.split(['#', '?']).next().unwrap_or_default();
+    let link = link.strip_suffix("index.html").unwrap_or(link);
+    let link = link.trim_matches('/');
+    link.strip_suffix(".html").unwrap_or(link).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stubs_are_written_inside_the_output_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("book");
+
+        write_stubs(&out, [("/old/page.html", "/new.html")].into_iter()).unwrap();
+        assert!(out.join("old/page.html").exists());
+
+        for from in ["/../index.html", "/old/../../index.html", "/"] {
+            assert!(write_stubs(&out, [(from, "/new.html")].into_iter()).is_err(), "{from}");
+        }
+        assert!(!dir.path().join("index.html").exists());
+    }
+}
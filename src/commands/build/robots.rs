@@ -0,0 +1,23 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::models::preprocessors_config::Robots;
+
+/// Writes the `robots.txt` of `robots` to `out`. Allowing crawlers, it links to the sitemap when
+/// the book has one and `site_url` gives its absolute URL.
+pub fn write_file(out: &Path, robots: Robots, site_url: Option<&str>) -> Result<()> {
+    let mut contents = String::from("User-agent: *\n");
+    match robots {
+        Robots::Allow => {
+            contents.push_str("Allow: /\n");
+            if let (Some(site_url), true) = (site_url, out.join("sitemap.xml").is_file()) {
+                contents.push_str(&format!("\nSitemap: {}/sitemap.xml\n", site_url.trim_end_matches('/')));
+            }
+        }
+        Robots::Disallow => contents.push_str("Disallow: /\n"),
+    }
+
+    fs::write(out.join("robots.txt"), contents).with_context(|| "Unable to write robots.txt")
+}
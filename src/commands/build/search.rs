@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::default_theme::links::absolute_page_href;
+use crate::models::{Chapter, Config};
+use crate::renderer::markdown::{headings, plain_text};
+
+/// File the search index is written to, next to the pages.
+pub const INDEX_FILE: &str = "searchindex.json";
+
+/// A chapter in the search index.
+#[derive(Debug, Serialize)]
+struct Document<'a> {
+    language: &'a str,
+    title: &'a str,
+    /// Absolute link to the page of the chapter.
+    url: String,
+    /// Text of the headings of the chapter, boosted by most search UIs.
+    headings: Vec<String>,
+    /// Plain text of the chapter.
+    body: String,
+}
+
+/// Writes the search index of the preprocessed chapters of each language of `sections` to
+/// `out`: the plain text of every chapter listed in the navigation, as a JSON array.
+pub fn write_index(
+    config: &Config,
+    sections: &[(String, Vec<Chapter>)],
+    out: &Path,
+) -> Result<()> {
+    let html = config.html_config();
+    let root_language = config.root_language();
+    let anchor_style = html.anchor_style.unwrap_or_default();
+
+    let mut documents = Vec::new();
+    for (language, chapters) in sections {
+        let language = language.as_str();
+        let language_dir = if root_language.as_deref() == Some(language) { "" } else { language };
+        for chapter in chapters {
+            let (Some(slug), Some(content)) = (&chapter.slug, &chapter.content) else {
+                continue;
+            };
+            if !chapter.in_toc() || chapter.redirect_to.is_some() {
+                continue;
+            }
+            documents.push(Document {
+                language,
+                title: &chapter.title,
+                url: absolute_page_href(language_dir, slug, html.trailing_slash),
+                headings: headings(content, anchor_style)
+                    .into_iter()
+                    .map(|heading| heading.text)
+                    .collect(),
+                body: plain_text(content),
+            });
+        }
+    }
+
+    let file = out.join(INDEX_FILE);
+    fs::write(&file, serde_json::to_string(&documents)?)
+        .with_context(|| format!("Unable to write {}", file.display()))?;
+    log::info!("Indexed {} chapters in {}", documents.len(), file.display());
+    Ok(())
+}
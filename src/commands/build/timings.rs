@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+/// How many of the slowest pages are logged.
+const SLOWEST_PAGES: usize = 10;
+
+/// How long each phase of a build took, in the order they ran, and how long each page took to
+/// render.
+#[derive(Debug, Default)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    pages: Vec<(String, Duration)>,
+}
+
+impl Timings {
+    /// Adds `duration` to `phase`, phases that run several times (once per language) accumulate.
+    pub fn record(&mut self, phase: &'static str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase, duration)),
+        }
+    }
+
+    pub fn record_page(&mut self, page: String, duration: Duration) {
+        self.pages.push((page, duration));
+    }
+
+    pub fn append(&mut self, other: Timings) {
+        for (phase, duration) in other.phases {
+            self.record(phase, duration);
+        }
+        self.pages.extend(other.pages);
+    }
+
+    /// Logs the slowest pages at the info level, shown with `-vv`.
+    pub fn log_slowest_pages(&self) {
+        if !log::log_enabled!(log::Level::Info) || self.pages.is_empty() {
+            return;
+        }
+
+        let mut pages = self.pages.iter().collect::<Vec<_>>();
+        pages.sort_by(|(_, a), (_, b)| b.cmp(a));
+        log::info!("Slowest pages:");
+        for (page, duration) in pages.into_iter().take(SLOWEST_PAGES) {
+            log::info!("{:>12}  {page}", format_duration(*duration));
+        }
+    }
+
+    pub fn print(&self) {
+        let total: Duration = self.phases.iter().map(|(_, duration)| *duration).sum();
+
+        println!("{:<20} {:>12}", "Phase", "Time");
+        for (phase, duration) in &self.phases {
+            println!("{:<20} {:>12}", phase, format_duration(*duration));
+        }
+        println!("{:<20} {:>12}", "total", format_duration(total));
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
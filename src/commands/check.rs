@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::diagnostic::{self, Diagnostic, MessageFormat};
+use crate::models::book::charpters_from_folder;
+use crate::models::Config;
+
+/// Compares the chapters of every language against the default language and reports what's
+/// missing or extra in each translation.
+/// With `only`, just that translation is checked, it has to be one of the other languages.
+pub fn execute(config: &Config, root: &Path, only: Option<&str>) -> Result<()> {
+    // The coverage summary is for humans, editors only get the diagnostics
+    let human = diagnostic::format() == MessageFormat::Human;
+    let languages = config.book.languages.clone().unwrap_or_default();
+    let Some(reference) = config.default_language() else {
+        if human {
+            println!("The book has a single language, there are no translations to check");
+        }
+        return Ok(());
+    };
+
+    // A typo would check nothing and pass
+    if let Some(only) = only {
+        if only == reference {
+            bail!("`{only}` is the reference language, the translations are checked against it");
+        }
+        if !languages.iter().any(|lang| lang == only) {
+            bail!("`{only}` isn't a language of the book, it has {}", languages.join(", "));
+        }
+    }
+
+    let reference_slugs = slugs(config, root, &reference)?;
+    if human {
+        println!(
+            "Translation coverage (reference: {reference}, {} chapters)",
+            reference_slugs.len()
+        );
+    }
+
+    let languages = languages
+        .iter()
+        .filter(|lang| **lang != reference && only.is_none_or(|only| only == lang.as_str()));
+    for lang in languages {
+        let lang_slugs = slugs(config, root, lang)?;
+        let missing = reference_slugs.difference(&lang_slugs).collect::<Vec<_>>();
+        let extra = lang_slugs.difference(&reference_slugs).collect::<Vec<_>>();
+        let translated = reference_slugs.len() - missing.len();
+        let coverage = if reference_slugs.is_empty() {
+            100.0
+        } else {
+            translated as f64 * 100.0 / reference_slugs.len() as f64
+        };
+
+        if human {
+            println!(
+                "  {lang}: {translated}/{} ({coverage:.0}%)",
+                reference_slugs.len()
+            );
+        }
+        for slug in missing {
+            Diagnostic::warning(format!("`{slug}` isn't translated to {lang}"))
+                .file(chapter_file(config, lang, slug))
+                .emit();
+        }
+        for slug in extra {
+            Diagnostic::warning(format!("`{slug}` doesn't exist in {reference}"))
+                .file(chapter_file(config, lang, slug))
+                .emit();
+        }
+    }
+
+    Ok(())
+}
+
+fn slugs(config: &Config, root: &Path, lang: &str) -> Result<BTreeSet<String>> {
+    let folder = config.book.src.join(lang);
+    let landing_page = config.build.clone().unwrap_or_default().landing_page;
+    let html = config.html_config();
+    let trailing_slash = html.trailing_slash;
+    let anchor_style = html.anchor_style.unwrap_or_default();
+    let chapters =
+        charpters_from_folder(&folder, root, landing_page, trailing_slash, anchor_style, false)?;
+
+    Ok(chapters.into_iter().filter_map(|chapter| chapter.slug).collect())
+}
+
+/// Where the chapter `slug` of `lang` is expected to be.
+fn chapter_file(config: &Config, lang: &str, slug: &str) -> PathBuf {
+    config.book.src.join(lang).join(format!("{slug}.md"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_another_declared_language_can_be_checked() {
+        let toml = "[rust]\n[book]\nlanguages = [\"en\", \"es\"]\n[language.en]\n[language.es]\n";
+        let config: Config = toml::from_str(toml).unwrap();
+
+        let undeclared = execute(&config, Path::new("."), Some("fr")).unwrap_err();
+        assert_eq!(undeclared.to_string(), "`fr` isn't a language of the book, it has en, es");
+        let reference = execute(&config, Path::new("."), Some("en")).unwrap_err();
+        assert!(reference.to_string().contains("`en` is the reference language"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Values completed at runtime by the scripts of the `completions` command, they depend on the
+//! book in the current directory, or the one of `--config`.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use clap_complete::Shell;
+
+use crate::models::book::charpters_from_folder;
+use crate::models::{book_root, Config};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Candidates {
+    /// Languages declared in `book.toml`
+    Language,
+    /// Slugs of the chapters
+    Chapter,
+}
+
+/// Prints the candidates of the book configured by `config_file` starting with `prefix`, one
+/// per line.
+pub fn execute(candidates: Candidates, prefix: &str, config_file: &Path) -> Result<()> {
+    // Completing must not fail loudly, outside of a book there's just nothing to complete
+    let Ok(config) = Config::from_disk(config_file) else {
+        return Ok(());
+    };
+    let values = match candidates {
+        Candidates::Language => config.book.languages.clone().unwrap_or_default().into_iter().collect(),
+        Candidates::Chapter => chapter_slugs(&config, book_root(config_file)),
+    };
+
+    for value in values.iter().filter(|value| value.starts_with(prefix)) {
+        println!("{value}");
+    }
+
+    Ok(())
+}
+
+fn chapter_slugs(config: &Config, root: &Path) -> BTreeSet<String> {
+    let languages = config.book.languages.clone().unwrap_or_else(|| vec![String::new()]);
+    let landing_page = config.build.clone().unwrap_or_default().landing_page;
+    let html = config.html_config();
+    let trailing_slash = html.trailing_slash;
+    let anchor_style = html.anchor_style.unwrap_or_default();
+
+    languages
+        .iter()
+        .filter_map(|lang| {
+            let folder = config.book.src.join(lang);
+            charpters_from_folder(&folder, root, landing_page, trailing_slash, anchor_style, false)
+                .ok()
+        })
+        .flatten()
+        .filter_map(|chapter| chapter.slug)
+        .collect()
+}
+
+/// Shell code appended to the generated completions so the flags taking languages or chapters
+/// ask `__complete` for their values. `None` for the shells only getting static completions.
+pub fn dynamic_script(shell: Shell, name: &str) -> Option<String> {
+    match shell {
+        Shell::Bash => Some(format!(
+            r#"
+_{name}_dynamic() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+        --chapter|-c|--only)
+            COMPREPLY=($(compgen -W "$({name} __complete chapter 2>/dev/null)" -- "$cur"))
+            return 0
+            ;;
+        --language)
+            COMPREPLY=($(compgen -W "$({name} __complete language 2>/dev/null)" -- "$cur"))
+            return 0
+            ;;
+    esac
+    _{name} "$@"
+}}
+complete -F _{name}_dynamic -o bashdefault -o default {name}
+"#
+        )),
+        Shell::Fish => Some(format!(
+            r#"
+complete -c {name} -n "__fish_seen_subcommand_from test" -l chapter -f -a "({name} __complete chapter 2>/dev/null)"
+complete -c {name} -n "__fish_seen_subcommand_from build" -l only -f -a "({name} __complete chapter 2>/dev/null)"
+complete -c {name} -n "__fish_seen_subcommand_from check" -l language -f -a "({name} __complete language 2>/dev/null)"
+"#
+        )),
+        _ => None,
+    }
+}
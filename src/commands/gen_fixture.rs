@@ -0,0 +1,72 @@
+//! Synthetic books for benchmarking the build, `mdbook-killer gen-fixture` with the `dev-tools`
+//! feature. The content only depends on the number of chapters, so two runs give the same book.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Sentences the paragraphs are made of, picked by the position of the paragraph.
+const SENTENCES: [&str; 8] = [
+    "The borrow checker makes sure every reference is valid for as long as it's used.",
+    "Iterators are lazy, nothing happens until they're consumed.",
+    "A trait describes what a type can do, generics let functions work with any of them.",
+    "Errors are values, `Result` makes handling them explicit.",
+    "Modules organize the code of a crate and control what's public.",
+    "Pattern matching checks that every case is handled at compile time.",
+    "Smart pointers like `Box` and `Rc` own the values they point to.",
+    "Cargo builds the crate, downloads its dependencies and runs its tests.",
+];
+
+/// Writes a book with `chapters` chapters to `dir`, with a `SUMMARY.md` listing them.
+pub fn execute(chapters: usize, dir: &Path) -> Result<()> {
+    let src = dir.join("src");
+    fs::create_dir_all(&src).with_context(|| format!("Unable to create {}", src.display()))?;
+    fs::write(
+        dir.join("book.toml"),
+        format!("[book]\ntitle = \"Fixture with {chapters} chapters\"\nsrc = \"src\"\n"),
+    )
+    .with_context(|| "Unable to write book.toml")?;
+
+    let mut summary = String::from("# Summary\n\n");
+    for index in 1..=chapters {
+        let file = format!("chapter-{index:05}.md");
+        writeln!(summary, "- [Chapter {index}]({file})")?;
+        fs::write(src.join(&file), chapter(index, chapters))
+            .with_context(|| format!("Unable to write {file}"))?;
+    }
+    fs::write(src.join("SUMMARY.md"), summary).with_context(|| "Unable to write SUMMARY.md")?;
+
+    log::info!("Wrote a book with {chapters} chapters to {}", dir.display());
+    Ok(())
+}
+
+/// Markdown of the chapter `index` out of `total`: sections with paragraphs, a list, a code
+/// block, a table and links to its neighbours.
+fn chapter(index: usize, total: usize) -> String {
+    let mut markdown = format!("---\ntitle: Chapter {index}\n---\n\n# Chapter {index}\n\n");
+    for section in 1..=4 {
+        let _ = writeln!(markdown, "## Section {index}.{section}\n");
+        for paragraph in 0..3 {
+            let sentences = (0..4)
+                .map(|sentence| SENTENCES[(index + section + paragraph + sentence) % SENTENCES.len()])
+                .collect::<Vec<_>>();
+            let _ = writeln!(markdown, "{}\n", sentences.join(" "));
+        }
+        let _ = writeln!(markdown, "- First point of section {section}\n- Second point\n- Third point\n");
+        let _ = writeln!(
+            markdown,
+            "```rust\nfn section_{section}(values: &[u32]) -> u32 {{\n    values.iter().map(|value| value * {index}).sum()\n}}\n```\n"
+        );
+    }
+    markdown.push_str("| Item | Value |\n|------|-------|\n| index | ");
+    let _ = writeln!(markdown, "{index} |\n| total | {total} |\n");
+    if index > 1 {
+        let _ = writeln!(markdown, "Previous: [Chapter {}](chapter-{:05}.md)\n", index - 1, index - 1);
+    }
+    if index < total {
+        let _ = writeln!(markdown, "Next: [Chapter {}](chapter-{:05}.md)", index + 1, index + 1);
+    }
+    markdown
+}
@@ -1,16 +1,46 @@
-use std::collections::HashMap;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use anyhow::{Context, Result};
+use clap::ValueEnum;
+use indexmap::IndexMap;
 
+use crate::diagnostic::Diagnostic;
 use crate::models::config_book::BookConfig;
 use crate::models::lang_config::{Language, LanguageConfig};
 use crate::models::Config;
 
-use super::Commands;
+/// Starting structure of a new book.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Template {
+    /// A single chapter
+    #[default]
+    Minimal,
+    /// An introduction followed by a few example chapters
+    Chapters,
+    /// A chapter in each of several languages
+    Multilingual,
+}
+
+/// File, title and markdown of the chapters of the `chapters` template.
+const EXAMPLE_CHAPTERS: [(&str, &str, &str); 3] = [
+    ("README.md", "Introduction", "# Introduction\n\nWhat this book is about and who it's for.\n"),
+    (
+        "getting-started.md",
+        "Getting started",
+        "# Getting started\n\nHow to install and set up what the book covers.\n",
+    ),
+    ("usage.md", "Usage", "# Usage\n\nThe everyday tasks, one section each.\n"),
+];
 
-pub fn execute(_theme: Option<String>, title: Option<String>, root_path: &PathBuf) -> Result<()> {
+pub fn execute(
+    _theme: Option<String>,
+    title: Option<String>,
+    template: Template,
+    init_git: bool,
+    root_path: &PathBuf,
+) -> Result<()> {
     log::debug!("Root Path: {root_path:?}");
     let title = title.clone().unwrap_or(get_text(
         "What is the title for this book?",
@@ -21,6 +51,16 @@ pub fn execute(_theme: Option<String>, title: Option<String>, root_path: &PathBu
         "My awesome description",
     )?;
     let lang = get_text("What is the default language of this book?", "en")?;
+    let mut languages = vec![lang.clone()];
+    if template == Template::Multilingual {
+        let others = get_text("Which other languages, separated by commas?", "es")?;
+        languages.extend(
+            others
+                .split(',')
+                .map(|other| other.trim().to_string())
+                .filter(|other| !other.is_empty() && *other != lang),
+        );
+    }
 
     let config = Config {
         book: BookConfig {
@@ -29,33 +69,76 @@ pub fn execute(_theme: Option<String>, title: Option<String>, root_path: &PathBu
             description: Some(description.clone()),
             src: "src".into(),
             language: Some(lang.clone()),
-            languages: Some(vec![lang.clone()]),
+            languages: Some(languages.clone()),
             text_direction: None,
         },
-        language: Some(LanguageConfig(HashMap::from_iter([(
-            lang.clone(),
-            Language {
-                name: lang.clone(),
-                title: Some(title.clone()),
-                authors: None,
-                description: Some(description.clone()),
-            },
-        )]))),
+        language: Some(LanguageConfig(IndexMap::from_iter(languages.iter().map(|lang| {
+            (
+                lang.clone(),
+                Language {
+                    name: lang.clone(),
+                    title: Some(title.clone()),
+                    authors: None,
+                    description: Some(description.clone()),
+                    ui: None,
+                    default: false,
+                },
+            )
+        })))),
         ..Default::default()
     };
     log::trace!("Config generated: {config:?}");
 
-    // Generate struct src folder
-    let lang_path = root_path.join("src").join(lang);
-    log::trace!("Book Path with Lang: {lang_path:?}");
-    std::fs::create_dir_all(&lang_path)?;
+    std::fs::create_dir_all(root_path)?;
     std::fs::File::create(root_path.join("book.toml"))?
-        .write_all(toml::to_string_pretty(&config).unwrap().as_bytes());
-    std::fs::File::create(lang_path.join("SUMMARY.md"))?.write_all(
-        format!("---\nog_title: {title}\nog_description: {description}\n---",).as_bytes(),
-    )?;
-    std::fs::File::create(lang_path.join("Readme.md"))?
-        .write_all(b"# This is a index Example\nWith description")?;
+        .write_all(toml::to_string_pretty(&config).unwrap().as_bytes())?;
+
+    // Generate struct src folder
+    for lang in &languages {
+        let lang_path = root_path.join("src").join(lang);
+        log::trace!("Book Path with Lang: {lang_path:?}");
+        std::fs::create_dir_all(&lang_path)?;
+        match template {
+            Template::Minimal | Template::Multilingual => {
+                std::fs::File::create(lang_path.join("SUMMARY.md"))?.write_all(
+                    format!("---\nog_title: {title}\nog_description: {description}\n---\n\n- [{title}](Readme.md)\n").as_bytes(),
+                )?;
+                std::fs::File::create(lang_path.join("Readme.md"))?
+                    .write_all(b"# This is a index Example\nWith description")?;
+            }
+            Template::Chapters => {
+                let mut summary = String::from("# Summary\n\n");
+                for (file, chapter_title, content) in EXAMPLE_CHAPTERS {
+                    summary.push_str(&format!("- [{chapter_title}]({file})\n"));
+                    std::fs::write(lang_path.join(file), content)?;
+                }
+                std::fs::write(lang_path.join("SUMMARY.md"), summary)?;
+            }
+        }
+    }
+    if init_git {
+        init_repository(root_path, &config)?;
+    }
+    Ok(())
+}
+
+/// Writes a `.gitignore` ignoring the build output and runs `git init`, only warning when git
+/// isn't installed or fails, the book itself is already created.
+fn init_repository(root_path: &Path, config: &Config) -> Result<()> {
+    let build_dir = config.build.clone().unwrap_or_default().build_dir;
+    let gitignore = format!("/{}\n", build_dir.display());
+    std::fs::write(root_path.join(".gitignore"), gitignore)
+        .with_context(|| "Unable to write .gitignore")?;
+
+    match Command::new("git").arg("init").arg("--quiet").current_dir(root_path).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => Diagnostic::warning(format!("`git init` failed with {status}")).emit(),
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            Diagnostic::warning("git isn't installed, the repository wasn't initialized").emit();
+        }
+        Err(error) => Diagnostic::warning(format!("Unable to run `git init`: {error}")).emit(),
+    }
+
     Ok(())
 }
 
@@ -0,0 +1,305 @@
+//! Translates the `book.toml` of an mdBook project into a configuration of mdbook-killer,
+//! reporting what couldn't be translated. The sources are left untouched, the `SUMMARY.md` is
+//! only read to report the parts of it mdbook-killer ignores.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use toml::{Table, Value};
+
+use crate::models::{BookNotFound, Config};
+
+/// File the configuration is written to without `--output`, next to the `book.toml` of mdBook.
+pub const DEFAULT_OUTPUT: &str = "mdbook-killer.toml";
+
+/// What becomes of an option of mdBook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    /// Same option, same meaning.
+    Keep,
+    /// Same option, only part of it is supported.
+    Partly(&'static str),
+    /// Another option means the same.
+    Move(&'static str),
+    /// Left out, with why.
+    Drop(&'static str),
+}
+
+fn rule(option: &str) -> Rule {
+    match option {
+        "book.title" | "book.authors" | "book.description" | "book.src" | "book.language"
+        | "book.languages" | "book.text-direction" | "language" | "build.build-dir"
+        | "rust.edition" | "output.html.no-section-label" | "output.html.git-repository-url"
+        | "output.html.git-repository-icon" | "output.html.input-404" | "output.html.print"
+        | "output.html.fold" | "output.html.redirect" => Rule::Keep,
+        "book.multilingual" => Rule::Drop(
+            "the languages are listed in `book.languages`, each one in its own folder of `src`",
+        ),
+        "build.create-missing" => Rule::Drop("missing chapter files are reported, not created"),
+        "build.use-default-preprocessors" => Rule::Drop("there are no preprocessors to turn off"),
+        "build.extra-watch-dirs" => {
+            Rule::Drop("`--poll` watches `book.toml`, the sources and the theme only")
+        }
+        "output.html.theme" => Rule::Partly(
+            "the handlebars templates aren't read, only `style.css`, `head.html`, `header.html` \
+             and `footer.html`",
+        ),
+        "output.html.curly-quotes" | "output.html.smart-punctuation" => {
+            Rule::Move("markdown.smart-punctuation")
+        }
+        "output.html.default-theme" | "output.html.preferred-dark-theme" => {
+            Rule::Drop("the color themes don't exist, the theme switches between light and dark")
+        }
+        "output.html.mathjax-support" => Rule::Drop("MathJax isn't supported"),
+        "output.html.copy-fonts" => {
+            Rule::Drop("no fonts are bundled, self-hosted fonts go in `[output.html.fonts]`")
+        }
+        "output.html.additional-css" | "output.html.additional-js" => Rule::Drop(
+            "not supported yet, `theme/head.html` can link the files, or the `css` and `scripts` \
+             of a chapter's frontmatter for a single page",
+        ),
+        "output.html.edit-url-template" => Rule::Drop("there are no edit links yet"),
+        "output.html.site-url" => Rule::Drop(
+            "mdBook's is the path the book is served from, mdbook-killer's is the absolute URL \
+             of the site, like `https://example.com`",
+        ),
+        "output.html.cname" => Rule::Drop("a `CNAME` file in `src` is copied to the output"),
+        "output.html.playground" => Rule::Partly("only `runnable` is used"),
+        "output.html.code" => Rule::Partly("`hidelines` isn't used"),
+        "output.html.search" => Rule::Partly(
+            "only `enable` is used, it writes a `searchindex.json` but there's no search box",
+        ),
+        _ if option.starts_with("preprocessor.") => Rule::Drop("mdBook preprocessors aren't run"),
+        _ if option.starts_with("output.") && !option.starts_with("output.html.") => {
+            Rule::Drop("only the HTML output is built")
+        }
+        _ => Rule::Drop("unknown option"),
+    }
+}
+
+/// The options of `config` rules apply to: the keys of its tables, the preprocessors and the
+/// outputs but `html` as a whole.
+fn options(config: &Table) -> Vec<(String, Value)> {
+    let mut options = Vec::new();
+    for (name, value) in config {
+        match (name.as_str(), value) {
+            ("book" | "build" | "rust", Value::Table(table)) => {
+                for (key, value) in table {
+                    options.push((format!("{name}.{key}"), value.clone()));
+                }
+            }
+            ("output", Value::Table(outputs)) => {
+                for (output, value) in outputs {
+                    match value {
+                        Value::Table(html) if output == "html" => options.extend(
+                            html.iter()
+                                .map(|(key, value)| (format!("output.html.{key}"), value.clone())),
+                        ),
+                        _ => options.push((format!("output.{output}"), value.clone())),
+                    }
+                }
+            }
+            ("preprocessor", Value::Table(preprocessors)) => {
+                for (preprocessor, value) in preprocessors {
+                    options.push((format!("preprocessor.{preprocessor}"), value.clone()));
+                }
+            }
+            _ => options.push((name.clone(), value.clone())),
+        }
+    }
+    options
+}
+
+/// Sets the dotted `option` of `table`, creating the tables on the way.
+fn insert(table: &mut Table, option: &str, value: Value) {
+    let mut keys = option.split('.').collect::<Vec<_>>();
+    let last = keys.pop().unwrap_or_default();
+    let mut table = table;
+    for key in keys {
+        let entry = table.entry(key).or_insert_with(|| Value::Table(Table::new()));
+        let Value::Table(inner) = entry else {
+            return;
+        };
+        table = inner;
+    }
+    table.insert(last.to_string(), value);
+}
+
+/// The configuration of mdbook-killer translated from the one of mdBook, with a line of the
+/// report for every option that wasn't kept as is.
+fn translate(mdbook: &Table) -> (Table, Vec<String>) {
+    let mut config = Table::new();
+    let mut report = Vec::new();
+
+    for (option, value) in options(mdbook) {
+        match rule(&option) {
+            Rule::Keep => insert(&mut config, &option, value),
+            Rule::Partly(note) => {
+                report.push(format!("{option}: kept, but {note}"));
+                insert(&mut config, &option, value);
+            }
+            Rule::Move(target) => {
+                report.push(format!("{option}: moved to {target}"));
+                insert(&mut config, target, value);
+            }
+            Rule::Drop(reason) => report.push(format!("{option}: left out, {reason}")),
+        }
+    }
+    // `Config` requires both tables
+    for table in ["book", "rust"] {
+        config.entry(table).or_insert_with(|| Value::Table(Table::new()));
+    }
+
+    (config, report)
+}
+
+/// A line of the report for every part of an mdBook `SUMMARY.md` mdbook-killer ignores: only the
+/// links of its list are chapters.
+fn summary_report(summary: &str) -> Vec<String> {
+    let mut report = Vec::new();
+    let mut depth = 0;
+    // The first `# Summary` is the title of the file in mdBook too
+    let mut seen_title = false;
+    let mut heading = None;
+    let mut link: Option<(String, String)> = None;
+
+    for event in Parser::new(summary) {
+        match event {
+            Event::Start(Tag::Item) => depth += 1,
+            Event::End(TagEnd::Item) => depth -= 1,
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((level, String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((level, title)) = heading.take() else {
+                    continue;
+                };
+                if level == HeadingLevel::H1 && !seen_title {
+                    seen_title = true;
+                } else {
+                    report.push(format!(
+                        "part title `{title}`: not shown, the navigation has no parts"
+                    ));
+                }
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                link = Some((dest_url.to_string(), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = &mut heading {
+                    title.push_str(&text);
+                } else if let Some((_, title)) = &mut link {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                let Some((target, title)) = link.take() else {
+                    continue;
+                };
+                if depth == 0 {
+                    report.push(format!(
+                        "chapter `{title}` ({target}): left out, only the links of the list are \
+                         chapters"
+                    ));
+                } else if target.is_empty() {
+                    report.push(format!("draft chapter `{title}`: not built"));
+                }
+            }
+            Event::Rule => report.push("separator: not shown".to_string()),
+            _ => {}
+        }
+    }
+
+    report
+}
+
+/// Writes the configuration of mdbook-killer translated from the `book.toml` of the mdBook
+/// project in `dir` to `output`, relative to `dir`, and prints what wasn't translated.
+pub fn execute(dir: &Path, output: &Path, force: bool) -> Result<()> {
+    let mdbook_file = dir.join("book.toml");
+    if !mdbook_file.exists() {
+        return Err(BookNotFound(mdbook_file).into());
+    }
+    let output = dir.join(output);
+    if output.exists() && !force {
+        bail!("{} already exists, pass --force to overwrite it", output.display());
+    }
+
+    let mdbook = fs::read_to_string(&mdbook_file)
+        .with_context(|| format!("Unable to read {}", mdbook_file.display()))?;
+    let mdbook: Table = toml::from_str(&mdbook)
+        .with_context(|| format!("Invalid configuration file {}", mdbook_file.display()))?;
+    let (config, report) = translate(&mdbook);
+    // A value of the wrong type is reported before anything is written
+    Value::Table(config.clone())
+        .try_into::<Config>()
+        .with_context(|| format!("{} can't be translated", mdbook_file.display()))?;
+
+    fs::write(&output, toml::to_string_pretty(&config)?)
+        .with_context(|| format!("Unable to write {}", output.display()))?;
+
+    let src = config
+        .get("book")
+        .and_then(|book| book.get("src"))
+        .and_then(Value::as_str)
+        .unwrap_or("src");
+    let summary_file = dir.join(src).join("SUMMARY.md");
+    let summary = fs::read_to_string(&summary_file).ok();
+    let summary_report = summary.as_deref().map(summary_report).unwrap_or_default();
+
+    println!("Wrote {}", output.display());
+    if report.is_empty() && summary_report.is_empty() {
+        println!("Every option was translated");
+    }
+    if !report.is_empty() {
+        println!("\nbook.toml:");
+        report.iter().for_each(|line| println!("  {line}"));
+    }
+    if !summary_report.is_empty() {
+        println!("\n{}:", summary_file.display());
+        summary_report.iter().for_each(|line| println!("  {line}"));
+    }
+    println!("\nBuild with `mdbook-killer --config {} build`", output.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_kept_moved_or_reported() {
+        let mdbook: Table = toml::from_str(
+            "[book]\ntitle = \"Book\"\nmultilingual = false\n\
+             [output.html]\ncurly-quotes = true\nmathjax-support = true\n\
+             [preprocessor.mermaid]\ncommand = \"mdbook-mermaid\"\n",
+        )
+        .unwrap();
+        let (config, report) = translate(&mdbook);
+
+        assert_eq!(config["book"]["title"].as_str(), Some("Book"));
+        assert_eq!(config["markdown"]["smart-punctuation"].as_bool(), Some(true));
+        assert!(config.get("preprocessor").is_none());
+        assert_eq!(report.len(), 4);
+        assert!(Value::Table(config).try_into::<Config>().is_ok());
+    }
+
+    #[test]
+    fn summary_parts_outside_the_list_are_reported() {
+        let summary = "# Summary\n\n[Introduction](README.md)\n\n# Guide\n\n\
+                       - [Start](start.md)\n- [Later]()\n\n---\n";
+
+        assert_eq!(
+            summary_report(summary),
+            [
+                "chapter `Introduction` (README.md): left out, only the links of the list are chapters",
+                "part title `Guide`: not shown, the navigation has no parts",
+                "draft chapter `Later`: not built",
+                "separator: not shown",
+            ]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use leptos::{provide_context, view, IntoView};
+
+use crate::default_theme::chapterpage::ChapterPage;
+use crate::default_theme::layout::Layout;
+use crate::default_theme::overrides::ThemeOverrides;
+use crate::models::{Chapter, Config};
+use crate::renderer::markdown::MarkdownSettings;
+use crate::renderer::render::render;
+use crate::renderer::ssg::SiteContext;
+
+use super::build::CSS_FILE;
+use super::CONFIG;
+
+/// Renders a single markdown document to a standalone HTML page printed on stdout.
+/// The page uses the theme of the book, if any, but has no navigation.
+pub async fn execute(file: Option<&Path>, theme_dir: Option<&Path>, config_file: &Path) -> Result<()> {
+    let markdown = match file {
+        Some(file) => std::fs::read_to_string(file)
+            .with_context(|| format!("Unable to read {}", file.display()))?,
+        None => {
+            let mut markdown = String::new();
+            std::io::stdin()
+                .read_to_string(&mut markdown)
+                .with_context(|| "Unable to read the markdown from stdin")?;
+            markdown
+        }
+    };
+
+    // A book.toml isn't required to render a single document, an invalid one is still an error
+    let config = Config::from_disk_or_default(config_file)?;
+    _ = CONFIG.write().await.insert(config.clone());
+
+    let theme_dir = match theme_dir {
+        Some(theme_dir) => theme_dir.to_path_buf(),
+        None => PathBuf::from(config.html_config().theme.unwrap_or("theme".to_string())),
+    };
+    let mut theme = ThemeOverrides::load(&theme_dir);
+    // There's no `style.css` next to stdout, inline it
+    let css = theme.css.take().unwrap_or(CSS_FILE.to_string());
+    theme.head = Some(format!("<style>{css}</style>{}", theme.head.unwrap_or_default()));
+
+    let site = SiteContext {
+        theme,
+        markdown: MarkdownSettings::from_config(&config),
+        ..Default::default()
+    };
+    let chapter = Chapter {
+        content: Some(markdown),
+        ..Default::default()
+    };
+
+    let html = render(
+        move || {
+            view! {
+                <Layout is_home=false>
+                    <div class="px-6">
+                        <ChapterPage chapter=chapter />
+                    </div>
+                </Layout>
+            }
+            .into_view()
+        },
+        move || provide_context(site),
+    )
+    .await;
+    print!("{html}");
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::convert::Infallible;
+use std::future::IntoFuture;
+use std::io::ErrorKind;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use axum::body::Body;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use futures::Stream;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tower_http::services::{ServeDir, ServeFile};
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::models::book::Book;
+use crate::models::Config;
+use crate::renderer::rewrite;
+
+use super::{build, watch};
+
+/// How many ports after the requested one `--retry-port` tries.
+const PORT_RETRIES: u16 = 10;
+
+/// Path of the stream of updates the served pages listen to with `--poll`.
+const LIVE_RELOAD_PATH: &str = "/__live-reload";
+
+/// Applies the updates of [`LIVE_RELOAD_PATH`]: a changed page swaps its `<main>` for the one
+/// of the new page, running its scripts again, anything else reloads it.
+const LIVE_RELOAD_SCRIPT: &str = r#"
+(() => {
+    const events = new EventSource('/__live-reload');
+    events.onmessage = async (event) => {
+        const update = JSON.parse(event.data);
+        if (update.reload) return location.reload();
+        if (!update.pages.includes(decodeURI(location.pathname))) return;
+        // The policy of `[output.html] csp` only allows the inline scripts of the old page
+        if (document.querySelector('meta[http-equiv="Content-Security-Policy"]')) {
+            return location.reload();
+        }
+        try {
+            const response = await fetch(location.href, { cache: 'no-store' });
+            const page = new DOMParser().parseFromString(await response.text(), 'text/html');
+            const main = page.querySelector('main');
+            if (!response.ok || !main) return location.reload();
+            // Parsed scripts don't run, copies of them do
+            for (const parsed of main.querySelectorAll('script')) {
+                const script = document.createElement('script');
+                for (const { name, value } of parsed.attributes) script.setAttribute(name, value);
+                script.textContent = parsed.textContent;
+                parsed.replaceWith(script);
+            }
+            document.querySelector('main').replaceWith(main);
+            document.title = page.title;
+        } catch (error) {
+            location.reload();
+        }
+    };
+})();
+"#;
+
+/// Builds the book and serves it until Ctrl+C.
+///
+/// With `retry_port`, a port already in use isn't an error, the next free one is used instead.
+/// With `watch`, see `--poll`, the book is rebuilt when its sources change and the open pages
+/// are updated.
+pub async fn execute(
+    config: &Config,
+    root: &Path,
+    out: &Path,
+    hostname: &str,
+    port: u16,
+    retry_port: bool,
+    watch: Option<watch::Polling<'_>>,
+) -> Result<()> {
+    let book = Book::from_config(config.clone(), root)?;
+    _ = build::execute(&book, out, &[], None, false).await?;
+    let html = build::output_dir(config, out, "html");
+
+    let listener = bind(hostname, port, retry_port).await?;
+    print_urls(hostname, listener.local_addr()?);
+
+    // `ServeDir` guesses the content type from the extension, answers range requests and sends
+    // `Last-Modified`. The build always writes a `404.html`, the book's own or the built-in one
+    let files = ServeDir::new(&html).not_found_service(ServeFile::new(html.join("404.html")));
+    let (updates, _) = broadcast::channel(16);
+    let mut app = Router::new().fallback_service(files);
+    if watch.is_some() {
+        let events = updates.clone();
+        app = app
+            .route(LIVE_RELOAD_PATH, get(move || live_reload_events(events.subscribe())))
+            .layer(middleware::map_response(inject_live_reload));
+    }
+    let app = app
+        // Browsers may keep the files but must check the modification time on every request,
+        // or a rebuild would show stale pages
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("no-cache"),
+        ));
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown_signal());
+    let Some(polling) = watch else {
+        return server.await.with_context(|| "The HTTP server stopped unexpectedly");
+    };
+
+    // Rebuilding in the same task as the server, it stops with it
+    tokio::select! {
+        result = server.into_future() => result.with_context(|| "The HTTP server stopped unexpectedly"),
+        result = watch::rebuild_on_change(polling, root, out, Some(&updates)) => result,
+    }
+}
+
+/// Stream of the updates of the rebuilds the served pages listen to, see
+/// [`watch::rebuild_on_change`].
+async fn live_reload_events(
+    receiver: broadcast::Receiver<String>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(update) => return Some((Ok(Event::default().data(update)), receiver)),
+                // A page too slow to keep up only misses old updates
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Adds [`LIVE_RELOAD_SCRIPT`] to the served pages, the built files don't have it. The
+/// `Content-Security-Policy` of `[output.html] csp` is extended to allow it.
+async fn inject_live_reload(response: Response) -> Response {
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    // Not modified and partial responses are left alone
+    if !is_html || response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let html = rewrite::allow_added_script(&String::from_utf8_lossy(&bytes), LIVE_RELOAD_SCRIPT);
+    let end = html.rfind("</body>").unwrap_or(html.len());
+    let html = format!("{}<script>{LIVE_RELOAD_SCRIPT}</script>{}", &html[..end], &html[end..]);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(html))
+}
+
+async fn bind(hostname: &str, port: u16, retry_port: bool) -> Result<TcpListener> {
+    let last = if retry_port { port.saturating_add(PORT_RETRIES) } else { port };
+
+    for candidate in port..=last {
+        match TcpListener::bind((hostname, candidate)).await {
+            Ok(listener) => {
+                if candidate != port {
+                    println!("Port {port} is in use, using {candidate} instead");
+                }
+                return Ok(listener);
+            }
+            Err(error) if error.kind() == ErrorKind::AddrInUse && candidate < last => continue,
+            Err(error) if error.kind() == ErrorKind::AddrInUse && !retry_port => {
+                return Err(error).with_context(|| {
+                    format!("Port {port} is in use, pass --retry-port to use the next free one")
+                });
+            }
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Unable to listen on {hostname}:{candidate}"));
+            }
+        }
+    }
+
+    unreachable!("binding the last candidate port always returns")
+}
+
+/// Resolves on Ctrl+C, or SIGTERM on Unix, so the server releases its port before exiting.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            log::error!("Unable to listen for Ctrl+C: {error}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => _ = signal.recv().await,
+            Err(error) => {
+                log::error!("Unable to listen for SIGTERM: {error}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+    println!("Shutting down");
+}
+
+fn print_urls(hostname: &str, addr: SocketAddr) {
+    if !addr.ip().is_unspecified() {
+        println!("Serving on http://{hostname}:{}", addr.port());
+        return;
+    }
+
+    println!("Serving on http://localhost:{}", addr.port());
+    match lan_ip() {
+        Some(ip) => println!("On your network: http://{}", SocketAddr::new(ip, addr.port())),
+        None => log::warn!("Couldn't find the LAN address of this machine"),
+    }
+}
+
+/// Finds the address other devices on the LAN can reach this machine at.
+/// Connecting an UDP socket doesn't send anything, it only picks the outgoing interface.
+fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
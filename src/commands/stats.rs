@@ -0,0 +1,143 @@
+//! Numbers about the size of a book, computed from its markdown without building it.
+
+use anyhow::Result;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::models::book::Book;
+
+/// Reading speed used for the estimated reading time.
+const WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counts {
+    chapters: usize,
+    words: usize,
+    code_blocks: usize,
+    images: usize,
+}
+
+impl Counts {
+    /// Counts the words of the prose of `content`, the code blocks aren't read.
+    fn add_chapter(&mut self, content: &str) {
+        self.chapters += 1;
+        let mut in_code_block = false;
+
+        for event in Parser::new_ext(content, Options::all()) {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => {
+                    in_code_block = true;
+                    self.code_blocks += 1;
+                }
+                Event::End(TagEnd::CodeBlock) => in_code_block = false,
+                Event::Start(Tag::Image { .. }) => self.images += 1,
+                Event::Text(text) | Event::Code(text) if !in_code_block => {
+                    self.words += text.split_whitespace().count();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add(&mut self, other: Counts) {
+        self.chapters += other.chapters;
+        self.words += other.words;
+        self.code_blocks += other.code_blocks;
+        self.images += other.images;
+    }
+
+    fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+}
+
+/// Prints the size of `book`, in total and per language.
+pub fn execute(book: &Book) -> Result<()> {
+    let mut total = Counts::default();
+    let mut languages = Vec::with_capacity(book.sections.len());
+
+    for section in &book.sections {
+        let mut counts = Counts::default();
+        for chapter in &section.chapters {
+            counts.add_chapter(chapter.content.as_deref().unwrap_or_default());
+        }
+        total.add(counts);
+        languages.push((section.language.as_str(), counts));
+    }
+
+    println!("{:<20} {:>10}", "Chapters", total.chapters);
+    println!("{:<20} {:>10}", "Words", total.words);
+    println!("{:<20} {:>10}", "Reading time", format_minutes(total.reading_minutes()));
+    println!("{:<20} {:>10}", "Code blocks", total.code_blocks);
+    println!("{:<20} {:>10}", "Images", total.images);
+
+    // A book without languages has a single section, already described by the total
+    if languages.len() > 1 {
+        println!();
+        println!("{:<20} {:>10} {:>10}", "Language", "Chapters", "Words");
+        for (language, counts) in languages {
+            println!("{:<20} {:>10} {:>10}", language, counts.chapters, counts.words);
+        }
+    }
+
+    Ok(())
+}
+
+fn format_minutes(minutes: usize) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes} min"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_code_blocks_are_counted_but_not_their_words() {
+        let mut counts = Counts::default();
+        counts.add_chapter(
+            "# Title\n\nSome `inline code` here.\n\n```rust\nfn main() {}\n```\n\n\
+             ![An image](image.png)\n",
+        );
+
+        assert_eq!(counts.chapters, 1);
+        // `Title`, `Some`, `inline`, `code`, `here.` and the alt text of the image
+        assert_eq!(counts.words, 7);
+        assert_eq!(counts.code_blocks, 1);
+        assert_eq!(counts.images, 1);
+    }
+
+    #[test]
+    fn sections_add_up_to_the_total() {
+        let mut first = Counts::default();
+        first.add_chapter("one two three");
+        let mut second = Counts::default();
+        second.add_chapter("four\n\n```\ncode\n```\n");
+        second.add_chapter("![](a.png) ![](b.png)");
+
+        let mut total = Counts::default();
+        total.add(first);
+        total.add(second);
+
+        assert_eq!(total.chapters, 3);
+        assert_eq!(total.words, 4);
+        assert_eq!(total.code_blocks, 1);
+        assert_eq!(total.images, 2);
+    }
+
+    #[test]
+    fn reading_time_is_rounded_up() {
+        let counts = |words| Counts {
+            words,
+            ..Counts::default()
+        };
+
+        assert_eq!(counts(0).reading_minutes(), 0);
+        assert_eq!(counts(1).reading_minutes(), 1);
+        assert_eq!(counts(WORDS_PER_MINUTE).reading_minutes(), 1);
+        assert_eq!(counts(WORDS_PER_MINUTE + 1).reading_minutes(), 2);
+        assert_eq!(format_minutes(45), "45 min");
+        assert_eq!(format_minutes(125), "2 h 5 min");
+    }
+}
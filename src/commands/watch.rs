@@ -0,0 +1,300 @@
+//! Rebuilds the book when its sources change, for `watch` and `serve --poll`.
+//!
+//! Changes are found by scanning the modification times of the sources every interval, which
+//! works on the network and container filesystems (Docker volumes, WSL mounts) that don't send
+//! change notifications.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use crate::default_theme::links::absolute_page_href;
+use crate::models::book::Book;
+use crate::models::Config;
+
+use super::{build, CONFIG};
+
+/// Parses the interval of `--poll`, like `500ms`, `2s` or `2` for seconds.
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = match value.strip_suffix("ms") {
+        Some(number) => (number, Duration::from_millis(1)),
+        None => (value.strip_suffix('s').unwrap_or(value), Duration::from_secs(1)),
+    };
+    let number = number
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| format!("`{value}` isn't an interval, use something like `500ms` or `2s`"))?;
+    if number == 0 {
+        return Err("The interval must be greater than zero".to_string());
+    }
+    Ok(unit * number)
+}
+
+/// What `--poll` checks and how the book is read again for every rebuild.
+#[derive(Debug, Clone, Copy)]
+pub struct Polling<'a> {
+    /// `book.toml`, read again for every rebuild.
+    pub config_file: &'a Path,
+    /// Whether a missing `config_file` means the default configuration, see
+    /// `--allow-missing-config`.
+    pub allow_missing_config: bool,
+    /// How often the sources are checked.
+    pub interval: Duration,
+}
+
+impl Polling<'_> {
+    fn load_config(&self) -> Result<Config> {
+        if self.allow_missing_config {
+            Config::from_disk_or_default(self.config_file)
+        } else {
+            Config::from_disk(self.config_file)
+        }
+    }
+}
+
+/// Builds the book, then rebuilds it every time its sources change until the process is
+/// stopped. A failed rebuild is logged and the previous output is kept.
+pub async fn execute(polling: Polling<'_>, root: &Path, out: &Path) -> Result<()> {
+    rebuild(polling, root, out).await?;
+    rebuild_on_change(polling, root, out, None).await
+}
+
+/// Rebuilds the book every time its sources change, checking them every `polling.interval`.
+///
+/// After every rebuild, `updates` gets what the open pages have to do, as JSON: either
+/// `{"pages": [...]}` with the pages whose content changed, when only chapters changed and the
+/// navigation is the same, or `{"reload": true}`.
+pub async fn rebuild_on_change(
+    polling: Polling<'_>,
+    root: &Path,
+    out: &Path,
+    updates: Option<&broadcast::Sender<String>>,
+) -> Result<()> {
+    let mut book = Book::from_config(polling.load_config()?, root)?;
+    // The output may be inside the sources, for a book without `book.toml`
+    let out = out.canonicalize().unwrap_or_else(|_| out.to_path_buf());
+    let scan = |config: &Config| Snapshot::scan(&sources(config, polling.config_file, root), &out);
+    let mut snapshot = scan(&book.config);
+    log::info!("Polling the sources for changes every {:?}", polling.interval);
+
+    loop {
+        tokio::time::sleep(polling.interval).await;
+        let current = scan(&book.config);
+        let changed = snapshot.changed(&current);
+        snapshot = current;
+        let Some(first) = changed.first() else {
+            continue;
+        };
+
+        match changed.len() {
+            1 => log::info!("{} changed, rebuilding", first.display()),
+            count => log::info!("{} and {} other files changed, rebuilding", first.display(), count - 1),
+        }
+        match rebuild(polling, root, &out).await {
+            Ok(rebuilt) => {
+                if let Some(updates) = updates {
+                    // Nobody listening isn't an error, no page is open
+                    _ = updates.send(update(&changed, &book, &rebuilt).to_string());
+                }
+                book = rebuilt;
+            }
+            Err(error) => log::error!("Unable to rebuild the book: {error:#}"),
+        }
+    }
+}
+
+/// Builds the book with the current `book.toml`, returning it.
+async fn rebuild(polling: Polling<'_>, root: &Path, out: &Path) -> Result<Book> {
+    let config = polling.load_config()?;
+    _ = CONFIG.write().await.insert(config.clone());
+
+    let book = Book::from_config(config, root)?;
+    build::execute(&book, out, &[], None, false).await?;
+    Ok(book)
+}
+
+/// What the open pages do after `changed` files turned `before` into `after`, see
+/// [`rebuild_on_change`].
+fn update(changed: &[PathBuf], before: &Book, after: &Book) -> serde_json::Value {
+    let is_chapter = |book: &Book, file: &PathBuf| {
+        book.sections
+            .iter()
+            .flat_map(|section| &section.chapters)
+            .any(|chapter| chapter.path.as_ref() == Some(file))
+    };
+    let only_chapters = changed.iter().all(|file| is_chapter(before, file) && is_chapter(after, file));
+    if before.config != after.config || !only_chapters || navigation(before) != navigation(after) {
+        return json!({ "reload": true });
+    }
+
+    let config = &after.config;
+    let root_language = config.root_language();
+    let trailing_slash = config.html_config().trailing_slash;
+    let mut pages = Vec::new();
+    for section in &after.sections {
+        let language = section.language.as_str();
+        let language_dir = if root_language.as_deref() == Some(language) { "" } else { language };
+        let changed_chapters = section
+            .chapters
+            .iter()
+            .filter(|chapter| chapter.path.as_ref().is_some_and(|path| changed.contains(path)));
+        let mut any_changed = false;
+        for chapter in changed_chapters {
+            if let Some(slug) = &chapter.slug {
+                pages.push(absolute_page_href(language_dir, slug, trailing_slash));
+                any_changed = true;
+            }
+        }
+        // The homepage shows the landing page and the excerpts, the print page every chapter
+        if any_changed {
+            let home = match language_dir {
+                "" => "/".to_string(),
+                language_dir => format!("/{language_dir}/"),
+            };
+            pages.push(format!("{home}index.html"));
+            pages.push(home);
+            pages.push(absolute_page_href(language_dir, "print", trailing_slash));
+        }
+    }
+    json!({ "pages": pages })
+}
+
+/// What the sidebar of every page shows: the chapters, their titles and their nesting.
+fn navigation(book: &Book) -> Vec<(&str, Option<&str>, &str, bool, usize)> {
+    book.sections
+        .iter()
+        .flat_map(|section| {
+            section.chapters.iter().map(|chapter| {
+                let slug = chapter.slug.as_deref();
+                (section.language.as_str(), slug, chapter.title.as_str(), chapter.in_toc(), chapter.depth)
+            })
+        })
+        .collect()
+}
+
+/// Files and directories whose changes require a rebuild: `book.toml`, the sources and the
+/// theme directory.
+fn sources(config: &Config, config_file: &Path, root: &Path) -> Vec<PathBuf> {
+    let theme = config.html_config().theme.unwrap_or("theme".to_string());
+    vec![config_file.to_path_buf(), root.join(&config.book.src), root.join(theme)]
+}
+
+/// Modification time of every file under some paths.
+#[derive(Debug, Default, PartialEq)]
+struct Snapshot(BTreeMap<PathBuf, SystemTime>);
+
+impl Snapshot {
+    /// Scans `paths`, leaving out everything inside `ignored`.
+    fn scan(paths: &[PathBuf], ignored: &Path) -> Self {
+        let mut snapshot = Self::default();
+        for path in paths {
+            snapshot.add(path, ignored);
+        }
+        snapshot
+    }
+
+    /// Adds `path`, and everything inside it for a directory. Paths that can't be read are
+    /// skipped, they may be removed while scanning.
+    fn add(&mut self, path: &Path, ignored: &Path) {
+        if path.starts_with(ignored) {
+            return;
+        }
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+        if !metadata.is_dir() {
+            if let Ok(modified) = metadata.modified() {
+                self.0.insert(path.to_path_buf(), modified);
+            }
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            self.add(&entry.path(), ignored);
+        }
+    }
+
+    /// Files created, modified or removed since `self`.
+    fn changed(&self, current: &Snapshot) -> Vec<PathBuf> {
+        let modified = current
+            .0
+            .iter()
+            .filter(|(path, time)| self.0.get(*path) != Some(time))
+            .map(|(path, _)| path.clone());
+        let removed = self.0.keys().filter(|path| !current.0.contains_key(*path)).cloned();
+        modified.chain(removed).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::book::LanguageSection;
+    use crate::models::Chapter;
+
+    #[test]
+    fn intervals_accept_milliseconds_and_seconds() {
+        assert_eq!(parse_interval("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_interval("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_interval("3"), Ok(Duration::from_secs(3)));
+        assert!(parse_interval("0s").is_err());
+        assert!(parse_interval("fast").is_err());
+    }
+
+    #[test]
+    fn changes_include_created_modified_and_removed_files() {
+        let time = SystemTime::UNIX_EPOCH;
+        let later = time + Duration::from_secs(1);
+        let before = Snapshot(BTreeMap::from([
+            (PathBuf::from("kept.md"), time),
+            (PathBuf::from("edited.md"), time),
+            (PathBuf::from("removed.md"), time),
+        ]));
+        let after = Snapshot(BTreeMap::from([
+            (PathBuf::from("kept.md"), time),
+            (PathBuf::from("edited.md"), later),
+            (PathBuf::from("created.md"), time),
+        ]));
+
+        assert_eq!(
+            before.changed(&after),
+            ["created.md", "edited.md", "removed.md"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn edited_chapters_update_their_page_the_homepage_and_the_print_page() {
+        let chapter = |slug: &str| Chapter {
+            title: slug.to_string(),
+            slug: Some(slug.to_string()),
+            path: Some(PathBuf::from(format!("src/{slug}.md"))),
+            ..Default::default()
+        };
+        let book = Book {
+            config: Config::default(),
+            root: PathBuf::from("."),
+            sections: vec![LanguageSection {
+                chapters: vec![chapter("index"), chapter("setup")],
+                ..Default::default()
+            }],
+        };
+
+        assert_eq!(
+            update(&[PathBuf::from("src/setup.md")], &book, &book),
+            json!({ "pages": ["/setup.html", "/index.html", "/", "/print.html"] })
+        );
+        assert_eq!(
+            update(&[PathBuf::from("book.toml")], &book, &book),
+            json!({ "reload": true })
+        );
+    }
+}
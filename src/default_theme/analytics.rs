@@ -0,0 +1,86 @@
+//! The `[output.html] analytics` script, loaded with the page or once the reader consents.
+
+use std::collections::BTreeMap;
+
+use leptos::{component, use_context, view, IntoView};
+
+use crate::default_theme::meta::RawHead;
+use crate::models::lang_config::UiStrings;
+use crate::models::preprocessors_config::Analytics;
+use crate::renderer::markdown::escape_attr;
+use crate::renderer::ssg::SiteContext;
+
+/// Loads the script of [`AnalyticsScript`] once the reader accepted, showing the banner until
+/// they answer.
+const CONSENT_SCRIPT: &str = "
+(() => {
+    const banner = document.getElementById('consent-banner');
+    const load = () => {
+        const script = document.createElement('script');
+        script.defer = true;
+        for (const [name, value] of Object.entries(JSON.parse(banner.dataset.analytics))) {
+            script.setAttribute(name, value);
+        }
+        document.head.appendChild(script);
+    };
+    const answer = localStorage.getItem('analytics-consent');
+    if (answer === 'granted') load();
+    if (answer !== null) return;
+    banner.hidden = false;
+    for (const button of banner.querySelectorAll('button[data-consent]')) {
+        button.addEventListener('click', () => {
+            localStorage.setItem('analytics-consent', button.dataset.consent);
+            banner.hidden = true;
+            if (button.dataset.consent === 'granted') load();
+        });
+    }
+})();
+";
+
+/// Loads the analytics script of the book. With `[output.html] consent`, the script is only
+/// added to the page after the reader accepted in the banner, their answer is kept in
+/// `localStorage` so the banner is shown once.
+#[component]
+pub fn AnalyticsScript() -> impl IntoView {
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    let Some(analytics) = site.analytics else {
+        return ().into_view();
+    };
+    let attributes = attributes(&analytics);
+    if !site.consent {
+        let attributes = attributes
+            .iter()
+            .map(|(name, value)| format!(r#" {name}="{}""#, escape_attr(value)))
+            .collect::<String>();
+        return view! { <RawHead html=format!("<script defer{attributes}></script>") /> }.into_view();
+    }
+
+    let ui = use_context::<UiStrings>().unwrap_or_else(UiStrings::spanish);
+    let attributes = serde_json::to_string(&attributes).unwrap_or_default();
+    view! {
+        <div id="consent-banner" class="consent-banner" role="region" aria-label=ui.consent_message.clone() data-analytics=attributes hidden>
+            <p>{ui.consent_message}</p>
+            <button type="button" data-consent="granted">{ui.consent_accept}</button>
+            <button type="button" data-consent="denied">{ui.consent_decline}</button>
+        </div>
+        <script>
+            {CONSENT_SCRIPT}
+        </script>
+    }
+    .into_view()
+}
+
+/// Attributes of the `<script>` tag, the ones with a name that isn't valid are left out.
+fn attributes(analytics: &Analytics) -> BTreeMap<&str, &str> {
+    let is_valid = |name: &str| {
+        !name.is_empty() && name.chars().all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+    };
+    let mut attributes = analytics
+        .attributes
+        .iter()
+        .filter(|(name, _)| is_valid(name))
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect::<BTreeMap<_, _>>();
+    attributes.insert("src", &analytics.src);
+    attributes
+}
@@ -1,27 +1,263 @@
-use leptos::{component, view, IntoView};
+use leptos::{component, use_context, view, IntoView};
 // use rscx_mdx::mdx::{Mdx, MdxComponentProps};
+use crate::models::lang_config::UiStrings;
 use crate::models::Chapter;
-use leptos_mdx::mdx::{Components, Mdx};
+use crate::renderer::markdown::{number_heading, render_markdown};
+use crate::renderer::ssg::SiteContext;
 
 #[component]
-pub fn ChapterPage(#[prop(into)] chapter: Chapter) -> impl IntoView {
+pub fn ChapterPage(
+    #[prop(into)] chapter: Chapter,
+    /// Language of the page, for the names in the dates.
+    #[prop(into, default = String::new())]
+    language: String,
+) -> impl IntoView {
     let content = chapter.content.clone();
     let content = content.unwrap();
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    // Written by the `{{#tabs}}` preprocessor
+    let has_tabs = content.contains(r#"class="code-tabs""#);
+    let shown = |date: &String| Some((date.clone(), site.dates.format(date, &language)?));
+    let published = chapter.date.as_ref().and_then(shown);
+    let updated = chapter.updated.as_ref().filter(|updated| chapter.date.as_ref() != Some(*updated)).and_then(shown);
+    let number = chapter.number.as_ref().map(ToString::to_string);
 
     view! {
-        <div class="markdown-container prose dark:prose-invert max-w-none">
-            <MarkdownRender content=content />
-        </div>
+        {site.reading_progress.then(|| view! { <ReadingProgress /> })}
+        <article class="markdown-container prose dark:prose-invert max-w-none">
+            <MarkdownRender content=content number=number />
+        </article>
+        {(published.is_some() || updated.is_some()).then(|| view! { <ChapterDates published=published updated=updated /> })}
+        {site.lightbox.then(|| view! { <Lightbox /> })}
+        {has_tabs.then(|| view! { <CodeTabs /> })}
+        {site.playground_edition.map(|edition| view! { <Playground edition=edition /> })}
     }
 }
 
+/// When the chapter was published and last updated, as ISO 8601 dates and their text.
 #[component]
-pub fn MarkdownRender(content: String) -> impl IntoView {
-    let components = Components::new();
+fn ChapterDates(published: Option<(String, String)>, updated: Option<(String, String)>) -> impl IntoView {
+    let ui = use_context::<UiStrings>().unwrap_or_else(UiStrings::spanish);
 
     view! {
-        <>
-            <Mdx source=content components=components/>
-        </>
+        <footer class="chapter-dates">
+            {published.map(|(iso, text)| view! { <span>{ui.published} " " <time datetime=iso>{text}</time></span> })}
+            {updated.map(|(iso, text)| view! { <span>{ui.last_updated} " " <time datetime=iso>{text}</time></span> })}
+        </footer>
+    }
+}
+
+/// Selects the tabs of [`CodeTabs`] and remembers the chosen label.
+const CODE_TABS_SCRIPT: &str = "
+(() => {
+    const select = (tab) => {
+        for (const other of tab.parentElement.querySelectorAll('[role=tab]')) {
+            const selected = other === tab;
+            other.setAttribute('aria-selected', String(selected));
+            other.tabIndex = selected ? 0 : -1;
+            document.getElementById(other.getAttribute('aria-controls')).hidden = !selected;
+        }
+    };
+    const selectLabel = (label) => {
+        for (const tab of document.querySelectorAll('.code-tabs [role=tab]')) {
+            if (tab.dataset.tab === label) select(tab);
+        }
+    };
+    for (const tab of document.querySelectorAll('.code-tabs [role=tab]')) {
+        tab.addEventListener('click', () => {
+            selectLabel(tab.dataset.tab);
+            localStorage.setItem('code-tab', tab.dataset.tab);
+        });
+        tab.addEventListener('keydown', (event) => {
+            const tabs = [...tab.parentElement.querySelectorAll('[role=tab]')];
+            const step = { ArrowRight: 1, ArrowLeft: -1 }[event.key];
+            if (!step) return;
+            const next = tabs[(tabs.indexOf(tab) + step + tabs.length) % tabs.length];
+            next.focus();
+            next.click();
+        });
+    }
+    const stored = localStorage.getItem('code-tab');
+    if (stored) selectLabel(stored);
+})();
+";
+
+/// Switches the tabs of the `{{#tabs}}` groups, by click or with the arrow keys. The label
+/// chosen is remembered and selected in every group having it, on every page.
+#[component]
+fn CodeTabs() -> impl IntoView {
+    view! {
+        <script>
+            {CODE_TABS_SCRIPT}
+        </script>
+    }
+}
+
+/// Adds the run buttons of [`Playground`], its settings are the data attributes of the tag.
+const PLAYGROUND_SCRIPT: &str = "
+(() => {
+    const { edition, run, unavailable } = document.currentScript.dataset;
+    const attempts = 3;
+    const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+    // `null` when the playground can't answer, compile errors are answers
+    const evaluate = async (code) => {
+        for (let attempt = 1; ; attempt++) {
+            try {
+                const response = await fetch('https://play.rust-lang.org/evaluate.json', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ version: 'stable', optimize: '0', edition, code }),
+                });
+                if (response.ok) return await response.json();
+            } catch (error) {}
+            if (attempt === attempts) return null;
+            await sleep(500 * 2 ** attempt);
+        }
+    };
+    for (const code of document.querySelectorAll('.markdown-container pre > code.language-rust')) {
+        const button = document.createElement('button');
+        button.type = 'button';
+        button.className = 'playground-run';
+        button.textContent = run;
+        const output = document.createElement('pre');
+        output.className = 'playground-output';
+        output.setAttribute('aria-live', 'polite');
+        output.hidden = true;
+        button.addEventListener('click', async () => {
+            button.disabled = true;
+            output.hidden = false;
+            output.classList.remove('playground-unavailable');
+            output.textContent = '…';
+            const answer = await evaluate(code.textContent);
+            if (answer === null) {
+                output.classList.add('playground-unavailable');
+                output.textContent = unavailable;
+            } else {
+                output.textContent = answer.error || answer.result || '';
+            }
+            button.disabled = false;
+        });
+        code.parentElement.after(button, output);
+    }
+})();
+";
+
+/// Adds a run button to the Rust code blocks, showing the output the Rust playground gives for
+/// them. Network failures and errors of the playground itself are retried with backoff and then
+/// reported as the playground being unavailable, never as an error of the code.
+#[component]
+fn Playground(edition: String) -> impl IntoView {
+    let ui = use_context::<UiStrings>().unwrap_or_else(UiStrings::spanish);
+
+    view! {
+        <script data-edition=edition data-run=ui.run_code data-unavailable=ui.playground_unavailable>
+            {PLAYGROUND_SCRIPT}
+        </script>
+    }
+}
+
+/// Opens the clicked images of [`Lightbox`] and closes the overlay.
+const LIGHTBOX_SCRIPT: &str = "
+(() => {
+    const images = document.querySelectorAll('.markdown-container img');
+    let overlay = null;
+    const close = () => {
+        overlay?.remove();
+        overlay = null;
+    };
+    const open = (image) => {
+        overlay = document.createElement('div');
+        overlay.className = 'lightbox';
+        overlay.setAttribute('role', 'dialog');
+        overlay.setAttribute('aria-modal', 'true');
+        const zoomed = document.createElement('img');
+        zoomed.src = image.currentSrc || image.src;
+        zoomed.alt = image.alt;
+        overlay.appendChild(zoomed);
+        overlay.addEventListener('click', close);
+        document.body.appendChild(overlay);
+    };
+    for (const image of images) {
+        if (image.closest('a')) continue;
+        image.classList.add('lightbox-image');
+        image.addEventListener('click', () => image.complete && open(image));
+    }
+    document.addEventListener('keydown', (event) => {
+        if (event.key === 'Escape') close();
+    });
+})();
+";
+
+/// Opens the images of the chapter in a full-screen overlay, closed by Esc or a click.
+/// Images inside links keep going to their target. The overlay shows the source the browser
+/// already picked, lazy images are only zoomed once loaded.
+#[component]
+fn Lightbox() -> impl IntoView {
+    view! {
+        <script>
+            {LIGHTBOX_SCRIPT}
+        </script>
+    }
+}
+
+/// Keeps the bar of [`ReadingProgress`] in sync with the scroll position.
+const READING_PROGRESS_SCRIPT: &str = "
+(() => {
+    const bar = document.getElementById('reading-progress');
+    const update = () => {
+        const scrollable = document.documentElement.scrollHeight - window.innerHeight;
+        bar.hidden = scrollable <= 0;
+        const progress = scrollable > 0 ? Math.min(window.scrollY / scrollable, 1) : 0;
+        bar.style.transform = `scaleX(${progress})`;
+        bar.setAttribute('aria-valuenow', Math.round(progress * 100));
+    };
+    window.addEventListener('scroll', update, { passive: true });
+    window.addEventListener('resize', update);
+    update();
+})();
+";
+
+/// Bar fixed at the top of the page filling up as the chapter is scrolled, hidden when the
+/// chapter fits on the screen. It isn't focusable, screen readers announce it as a progress bar.
+#[component]
+fn ReadingProgress() -> impl IntoView {
+    let ui = use_context::<UiStrings>().unwrap_or_else(UiStrings::spanish);
+
+    view! {
+        <div
+            id="reading-progress"
+            role="progressbar"
+            aria-label=ui.reading_progress
+            aria-valuemin="0"
+            aria-valuemax="100"
+            aria-valuenow="0"
+            hidden
+            class="fixed top-0 left-0 z-50 h-1 w-full bg-orange-500 origin-left scale-x-0 motion-safe:transition-transform"
+        ></div>
+        <script>
+            {READING_PROGRESS_SCRIPT}
+        </script>
+    }
+}
+
+#[component]
+pub fn MarkdownRender(
+    content: String,
+    /// Prefix for the heading anchors, used when several chapters share a page.
+    #[prop(optional, into)]
+    id_prefix: Option<String>,
+    /// Section number of the chapter, shown before its first heading.
+    #[prop(default = None)]
+    number: Option<String>,
+) -> impl IntoView {
+    let settings = use_context::<SiteContext>().unwrap_or_default().markdown;
+    let html = render_markdown(&content, id_prefix.as_deref(), &settings);
+    let html = match number {
+        Some(number) => number_heading(&html, &number),
+        None => html,
+    };
+
+    view! {
+        <div inner_html=html />
     }
 }
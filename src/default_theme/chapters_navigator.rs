@@ -1,35 +1,43 @@
-use std::{collections::HashMap, env::current_dir, fs::read_to_string, path::Path};
-use leptos::{component, view, CollectView, IntoView};
+use std::{collections::HashMap, sync::Arc};
+use leptos::{component, use_context, view, CollectView, IntoView};
+use crate::default_theme::links::absolute_page_href;
+use crate::models::lang_config::UiStrings;
 use crate::models::Chapter;
+use crate::renderer::markdown::escape_attr;
+use crate::renderer::ssg::SiteContext;
 use super::custom_component::CustomComponent;
 
 
 
 #[component]
 pub fn ChaptersNavigator(
-    #[prop()] chapters: Vec<Chapter>,
+    #[prop(into)] chapters: Arc<[Chapter]>,
     #[prop()] language: String,
 ) -> impl IntoView {
-    println!("{:?}", current_dir());
-    let chapter_navigator = read_to_string("./theme/chapter_navigator.html").ok();
-    let chapter_navigator_item = read_to_string("./theme/chapter_navigator_item.html").ok();
-
-    let props = chapters.clone().into_iter().map(|chapter| {
-        let link = if !language.is_empty() {
-            format!("/{}.html", chapter.slug.unwrap())
-        } else {
-            format!("{}/{}.html", language.clone(), chapter.slug.unwrap())
-        };
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    let chapter_navigator = site.theme.chapter_navigator.clone();
+    let chapter_navigator_item = site.theme.chapter_navigator_item.clone();
+    let trailing_slash = site.trailing_slash;
+    let label = use_context::<UiStrings>()
+        .unwrap_or_else(UiStrings::spanish)
+        .table_of_contents
+        .unwrap_or_default();
+
+    let props = chapters.iter().filter(|chapter| chapter.in_toc()).map(|chapter| {
+        let slug = chapter.slug.as_deref().unwrap();
+        let link = absolute_page_href(site.language_dir(&language), slug, trailing_slash);
 
-        (link, chapter.title)
+        (link, chapter.numbered_title())
     })
-    .collect::<HashMap<String, String>>();
+    // In the order of the book, a map would shuffle the sidebar
+    .collect::<Vec<(String, String)>>();
 
     let chapter_navigator_items = if let Some(chapter_navigator_item) = chapter_navigator_item {
         props.into_iter().map(|(link, title)| {
             let mut custom_prop = HashMap::<String, String>::new();
-            custom_prop.insert("link".to_string(), link);
-            custom_prop.insert("title".to_string(), title);
+            // The template is raw HTML, unlike the values
+            custom_prop.insert("link".to_string(), escape_attr(&link));
+            custom_prop.insert("title".to_string(), escape_attr(&title));
 
             view!{
                 <CustomComponent props=custom_prop content=chapter_navigator_item.clone()  />
@@ -44,7 +52,6 @@ pub fn ChaptersNavigator(
     };
 
     let chapter_navigator_items = chapter_navigator_items.render_to_string();
-    println!("Existe o no? {:?}", chapter_navigator);
 
     let mut props = HashMap::<String,String>::new();
     props.insert("links".to_string(), chapter_navigator_items.to_string());
@@ -53,34 +60,130 @@ pub fn ChaptersNavigator(
         <>
             {if let Some(chapter_navigator) = chapter_navigator {
                 view!{
-                    <nav>
+                    <nav id="sidebar" aria-label=label>
                         <CustomComponent props=props content=chapter_navigator  />
-                        "Cosa"
                     </nav>
                 }
             }else{
                 view!{
-                    <nav class="dark:bg-[#101010] fixed left-0 min-w-52 border-r border-gray-700 h-full py-2">
+                    <nav id="sidebar" aria-label=label class="dark:bg-[#101010] fixed left-0 border-r border-gray-700 h-full py-2">
                     {
-                        chapters.into_iter().map(|chapter| {
-                            let link = if !language.is_empty() {
-                                format!("/{}.html", chapter.slug.unwrap())
-                            }else {
-                                format!("{}/{}.html", language.clone(), chapter.slug.unwrap())
-                            };
+                        chapters.iter().filter(|chapter| chapter.in_toc()).map(|chapter| {
+                            let slug = chapter.slug.as_deref().unwrap();
+                            let link = absolute_page_href(site.language_dir(&language), slug, trailing_slash);
 
                             view! {
                             <div class="px-2 py-1">
-                                <a href=link >{chapter.title}</a>
-                                "XD"
+                                <a href=link >{chapter.numbered_title()}</a>
                             </div>
                             }
                         }).collect_view()
                     }
+                    <SidebarResizeHandle />
                     </nav>
                 }
             }
         }
         </>
     }
-}
\ No newline at end of file
+}
+/// Resizes the sidebar from the handle of [`SidebarResizeHandle`].
+const RESIZE_SCRIPT: &str = "
+(() => {
+    const handle = document.currentScript.previousElementSibling;
+    const root = document.documentElement;
+    const resize = (width) => {
+        width = Math.round(Math.min(Math.max(width, 150), 600));
+        root.style.setProperty('--sidebar-width', width + 'px');
+        localStorage.setItem('sidebar-width', width);
+    };
+    const current = () => handle.parentElement.getBoundingClientRect().width;
+    handle.addEventListener('pointerdown', (event) => {
+        event.preventDefault();
+        handle.setPointerCapture(event.pointerId);
+        const move = (event) => resize(event.clientX);
+        handle.addEventListener('pointermove', move);
+        handle.addEventListener('pointerup', () => handle.removeEventListener('pointermove', move), { once: true });
+    });
+    handle.addEventListener('keydown', (event) => {
+        if (event.key === 'ArrowLeft') resize(current() - 16);
+        if (event.key === 'ArrowRight') resize(current() + 16);
+    });
+})();
+";
+
+/// Border of the sidebar dragged to resize it, the width is kept in `localStorage`. Arrow keys
+/// resize it too once focused.
+#[component]
+fn SidebarResizeHandle() -> impl IntoView {
+    view! {
+        <div class="sidebar-resize-handle" role="separator" aria-orientation="vertical" aria-controls="sidebar" tabindex="0"></div>
+        <script>
+            {RESIZE_SCRIPT}
+        </script>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use leptos::provide_context;
+    use leptos::ssr::render_to_string;
+
+    use super::*;
+    use crate::default_theme::overrides::ThemeOverrides;
+
+    /// The chapters a `recipes/*.md` entry expands to, sorted by name.
+    fn recipes() -> Arc<[Chapter]> {
+        ('a'..='l')
+            .map(|name| Chapter {
+                title: name.to_string(),
+                slug: Some(format!("recipes/{name}")),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Where the links of `chapters` are in `html`, in the order of the chapters.
+    fn link_positions(html: &str, chapters: &[Chapter]) -> Vec<usize> {
+        chapters
+            .iter()
+            .map(|chapter| {
+                let href = format!("/{}.html", chapter.slug.as_deref().unwrap());
+                html.find(&href).unwrap_or_else(|| panic!("no link to {href}"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_sidebar_lists_the_chapters_in_order() {
+        let chapters = recipes();
+        let shown = Arc::clone(&chapters);
+        let html =
+            render_to_string(move || view! { <ChaptersNavigator chapters=shown language=String::new() /> });
+
+        let positions = link_positions(&html, &chapters);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{html}");
+    }
+
+    #[test]
+    fn the_sidebar_template_lists_the_chapters_in_order() {
+        let chapters = recipes();
+        let shown = Arc::clone(&chapters);
+        let site = SiteContext {
+            theme: ThemeOverrides {
+                chapter_navigator: Some("<ul>{links}</ul>".to_string()),
+                chapter_navigator_item: Some(r#"<li><a href="{link}">{title}</a></li>"#.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let html = render_to_string(move || {
+            provide_context(site);
+            view! { <ChaptersNavigator chapters=shown language=String::new() /> }
+        });
+
+        assert!(html.contains(r#"<li><a href="/recipes/a.html">a</a></li>"#), "{html}");
+        let positions = link_positions(&html, &chapters);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{html}");
+    }
+}
@@ -0,0 +1,21 @@
+use leptos::{component, view, IntoView};
+
+use crate::default_theme::{chapterpage::MarkdownRender, layout::Layout};
+
+/// Body of the not-found page when the book has no `404.md`.
+pub const NOT_FOUND: &str = "# Page not found\n\nThe page you are looking for doesn't exist. [Go back to the start](/).\n";
+
+/// Page that isn't a chapter, like the not-found or offline pages, rendered from `content`
+/// markdown inside the theme shell. Its links must be absolute, it's served from any path.
+#[component]
+pub fn StandalonePage(#[prop(into)] content: String, #[prop(into)] language: String) -> impl IntoView {
+    view! {
+        <Layout is_home=false wide=false language=language>
+            <div class="px-6">
+                <article class="markdown-container prose dark:prose-invert max-w-none">
+                    <MarkdownRender content=content />
+                </article>
+            </div>
+        </Layout>
+    }
+}
@@ -1,24 +1,48 @@
-use std::{collections::HashMap, fs::read_to_string};
+use std::{collections::HashMap, sync::Arc};
 
-use leptos::{component, view, IntoView};
+use leptos::{component, use_context, view, IntoView};
 
 use crate::{
-    default_theme::{chapterpage::ChapterPage, chapters_navigator::ChaptersNavigator, custom_component::CustomComponent, layout::Layout},
+    default_theme::{chapterpage::ChapterPage, chapters_navigator::ChaptersNavigator, custom_component::CustomComponent, layout::Layout, pagination::Pagination, structured_data::StructuredData},
+    default_theme::links::absolute_page_href,
     models::Chapter,
+    renderer::{markdown::excerpt, ssg::SiteContext},
 };
 
+/// Longest description generated from the first paragraph, what search engines show.
+const DESCRIPTION_LENGTH: usize = 160;
+
 #[component]
 pub fn Homepage(
     #[prop(optional)] chapter: Option<Chapter>,
-    #[prop()] chapters: Vec<Chapter>,
+    #[prop(into)] chapters: Arc<[Chapter]>,
     #[prop()] language: String,
+    /// Whether this is the `index.html` of the language, the only page listing the chapters.
+    #[prop(optional)]
+    is_home: bool,
 ) -> impl IntoView {
     let first_chapter = chapters.first().unwrap().clone();
-    let chapter_body = read_to_string("./theme/chapter_body.html").ok();
+    // The assets of the chapter shown, the landing page's on the homepage
+    let shown = chapter.as_ref().unwrap_or(&first_chapter);
+    let (page_css, page_scripts) = (shown.css_links.clone(), shown.script_links.clone());
+    let current = chapter.as_ref().and_then(|chapter| chapter.slug.clone());
+    let chapter_body = use_context::<SiteContext>().unwrap_or_default().theme.chapter_body;
+    // The homepage is described by the book-level description
+    let page_description = chapter.as_ref().and_then(|chapter| {
+        chapter.description.clone().or_else(|| excerpt(chapter.content.as_deref()?, DESCRIPTION_LENGTH))
+    });
 
     view! {
-        <Layout is_home=true wide=false language=language.clone()>
-            <ChaptersNavigator chapters=chapters.clone() language=language />
+        <Layout
+            is_home=true
+            wide=false
+            language=language.clone()
+            page_description=page_description
+            page_css=page_css
+            page_scripts=page_scripts
+        >
+            <StructuredData chapter=chapter.clone() language=language.clone() />
+            <ChaptersNavigator chapters=Arc::clone(&chapters) language=language.clone() />
             {
                 if let Some(chapter_body) = chapter_body {
                     let props = HashMap::<String, String>::new();
@@ -30,10 +54,12 @@ pub fn Homepage(
                     }
                 } else {
                     view!{
-                        <div class="ml-52 px-6">
+                        <div class="sidebar-offset px-6">
                             <div class="flex w-full flex-row flex-1 items-center mt-6">
-                                <ChapterPage chapter=chapter.unwrap_or(first_chapter) />
+                                <ChapterPage chapter=chapter.unwrap_or(first_chapter) language=language.clone() />
                             </div>
+                            {is_home.then(|| view! { <ChapterListing chapters=Arc::clone(&chapters) current=current.clone() language=language.clone() /> })}
+                            {current.map(|current| view! { <Pagination chapters=Arc::clone(&chapters) current=current language=language.clone() /> })}
                         </div>
                     }
                 }
@@ -41,3 +67,31 @@ pub fn Homepage(
         </Layout>
     }
 }
+
+/// The chapters of the navigation other than `current`, each one with its excerpt.
+#[component]
+fn ChapterListing(
+    chapters: Arc<[Chapter]>,
+    current: Option<String>,
+    language: String,
+) -> impl IntoView {
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    let listed = chapters
+        .iter()
+        .filter(|chapter| chapter.in_toc() && chapter.slug != current)
+        .map(|chapter| {
+            let slug = chapter.slug.as_deref().unwrap_or_default();
+            let link = absolute_page_href(site.language_dir(&language), slug, site.trailing_slash);
+            view! {
+                <li class="chapter-listing-item">
+                    <a href=link>{chapter.numbered_title()}</a>
+                    {chapter.excerpt.clone().map(|excerpt| view! { <div class="chapter-excerpt" inner_html=excerpt /> })}
+                </li>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (!listed.is_empty()).then(|| view! {
+        <ul class="chapter-listing">{listed}</ul>
+    })
+}
@@ -1,12 +1,27 @@
 use chrono::Datelike;
 use futures::executor::block_on;
 use leptos::Children;
-use leptos::{component, view, IntoView};
+use leptos::{component, provide_context, use_context, view, IntoView};
 
 use crate::commands::CONFIG;
 use crate::default_theme::meta::Head;
 use crate::default_theme::meta::Html;
+use crate::default_theme::analytics::AnalyticsScript;
+use crate::default_theme::meta::RawHead;
+use crate::default_theme::overrides::fill;
+use crate::default_theme::repository::RepositoryLink;
+use crate::models::lang_config::UiStrings;
 use crate::models::Config;
+use crate::default_theme::links::{absolute_page_href, page_from_file};
+use crate::renderer::ssg::{SiteContext, SsgContext};
+
+/// Height in pixels of the sticky header of the default theme, see `[output.html] header-height`.
+const DEFAULT_HEADER_HEIGHT: u16 = 64;
+/// Width in pixels of the sidebar of the default theme, see `[output.html] sidebar-width`.
+const DEFAULT_SIDEBAR_WIDTH: u16 = 208;
+/// Registers the service worker of `[output.html] offline`.
+const REGISTER_WORKER_SCRIPT: &str =
+    "if ('serviceWorker' in navigator) { navigator.serviceWorker.register('/sw.js'); }";
 
 fn get_year() -> i32 {
     chrono::Utc::now().year()
@@ -22,13 +37,52 @@ pub fn Layout(
     #[prop(into, default = false)] is_home: bool,
     #[prop(into, default = "".to_string())] language: String,
     #[prop(into, default = false)] wide: bool,
+    /// Description of the page, the book's description is used without it.
+    #[prop(default = None)] page_description: Option<String>,
+    /// Stylesheets of the page only, after the one of the book.
+    #[prop(default = Vec::new())] page_css: Vec<String>,
+    /// Scripts of the page only, deferred.
+    #[prop(default = Vec::new())] page_scripts: Vec<String>,
     children: Children,
 ) -> impl IntoView {
     let config = block_on(fetch_config());
-    let title = config.book.title.unwrap();
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    // With `site-url` set, the canonical link follows the configured URL style
+    let canonical = config.html_config().site_url.map(|site_url| {
+        let site_url = site_url.trim_end_matches('/');
+        match use_context::<SsgContext>() {
+            Some(ctx) if !is_home => {
+                let page = page_from_file(&ctx.path);
+                format!("{site_url}{}", absolute_page_href(site.language_dir(&language), page, site.trailing_slash))
+            }
+            _ => format!("{site_url}/"),
+        }
+    });
+    let theme = site.theme;
+    let stylesheet = site.stylesheet;
+    let font_preload = site.font_preload;
+    let title = config.book.title.clone().unwrap_or_default();
     let title_clone = title.clone();
-    let description = config.book.description.unwrap();
+    let description = page_description
+        .or_else(|| config.book.description.clone())
+        .unwrap_or_default();
     let description_clone = title.clone();
+    let authors = config.get_localized_authors((!language.is_empty()).then_some(&language));
+    let authors_clone = authors.clone();
+    let ui = config.get_localized_ui((!language.is_empty()).then_some(&language));
+    // The pages inside the layout use the same strings
+    provide_context(ui.clone());
+
+    // Headings reached through an anchor must not end up under the sticky header
+    let scroll_margin = format!(
+        "[id] {{ scroll-margin-top: {}px; }}",
+        config.html_config().header_height.unwrap_or(DEFAULT_HEADER_HEIGHT)
+    );
+    // Readers resizing or collapsing the sidebar keep their choice, applied before the first paint
+    let sidebar_width = format!(
+        ":root {{ --sidebar-width: {}px; }}",
+        config.html_config().sidebar_width.unwrap_or(DEFAULT_SIDEBAR_WIDTH)
+    );
 
     let language = if language.is_empty() {
         "en".to_string()
@@ -48,6 +102,10 @@ pub fn Layout(
             <meta property="og:title" content=title.clone()/>
             <meta name="description" content=description.clone()/>
             <meta property="og:description" content=description.clone()/>
+            {join_authors(&authors).map(|authors| view! {
+                <meta name="author" content=authors.clone()/>
+                <meta property="article:author" content=authors/>
+            })}
             <meta
                 property="og:site_name"
                 content=format!("Blog de Rust Lang en Español {}", get_year())
@@ -57,7 +115,10 @@ pub fn Layout(
             {if is_home {
                 view! {
                     <>
-                        <link rel="canonical" href="https://blog.rustlang-es.org"/>
+                        <link
+                            rel="canonical"
+                            href=canonical.clone().unwrap_or_else(|| "https://blog.rustlang-es.org".to_string())
+                        />
                         <meta
                             property="og:image"
                             content=format!("https://rustlang-es.org/{slug}")
@@ -71,7 +132,10 @@ pub fn Layout(
             } else {
                 view! {
                     <>
-                        <link rel="canonical" href=format!("https://rustlang-es.org/{slug}")/>
+                        <link
+                            rel="canonical"
+                            href=canonical.clone().unwrap_or_else(|| format!("https://rustlang-es.org/{slug}"))
+                        />
                         <meta
                             property="og:image"
                             content=format!("https://rustlang-es.org/{slug}.png")
@@ -87,12 +151,18 @@ pub fn Layout(
             <meta name="twitter:card" content="summary_large_image"/>
             <meta name="twitter:site" content="@rustlang"/>
             <link rel="icon" href="/LogoSegunMichael-134de58fcd9af94e.ico"/>
-            {if cfg!(debug_assertions) {
-                view! { <link rel="stylesheet" href="/style.css"/> }
-            } else {
-                view! { <link rel="stylesheet" href="/style.css"/> }
-                // view! { <link rel="stylesheet" href="https://blog.rustlang-es.org/output.css"/> }
-            }}
+            {font_preload.map(|href| view! { <link as="font" rel="preload" href=href crossorigin="anonymous"/> })}
+            {stylesheet.map(|href| view! { <link rel="stylesheet" href=href/> })}
+            {page_css.into_iter().map(|href| view! { <link rel="stylesheet" href=href/> }).collect::<Vec<_>>()}
+            {page_scripts.into_iter().map(|src| view! { <script src=src defer></script> }).collect::<Vec<_>>()}
+            {site.no_index.then(|| view! { <meta name="robots" content="noindex"/> })}
+            {site.offline.then(|| view! {
+                <link rel="manifest" href="/manifest.webmanifest"/>
+                <script>
+                    {REGISTER_WORKER_SCRIPT}
+                </script>
+            })}
+            // view! { <link rel="stylesheet" href="https://blog.rustlang-es.org/output.css"/> }
 
             <style>
                 {"
@@ -101,6 +171,19 @@ pub fn Layout(
                 }
                 "}
             </style>
+            <style>{scroll_margin}</style>
+            <style>{sidebar_width}</style>
+            <script>
+                {"
+                (() => {
+                    const width = localStorage.getItem('sidebar-width');
+                    if (width) document.documentElement.style.setProperty('--sidebar-width', width + 'px');
+                    if (localStorage.getItem('sidebar-collapsed') === 'true') {
+                        document.documentElement.classList.add('sidebar-collapsed');
+                    }
+                })();
+                "}
+            </script>
             <script type="module">
                 {"
                 const API = 'https://rust-lang-en-espanol-api.shuttleapp.rs';
@@ -113,7 +196,29 @@ pub fn Layout(
                 "}
             </script>
         </Head>
-        <Header title={title_clone} description={description_clone} />
+        {site.additional_head.map(|head| view! { <RawHead html=head /> })}
+        {theme.head.map(|head| view! { <RawHead html=head /> })}
+        <a
+            href="#content"
+            class="sr-only focus:not-sr-only focus:fixed focus:top-2 focus:left-2 focus:z-50 focus:p-2 focus:bg-white focus:text-black"
+        >
+            {ui.skip_to_content.clone().unwrap_or_default()}
+        </a>
+        {match theme.header {
+            Some(header) => {
+                let header = fill(&header, &[("title", title_clone.as_str()), ("description", description_clone.as_str())]);
+                view! { <div inner_html=header /> }.into_view()
+            }
+            None => view! {
+                <Header
+                    title={title_clone}
+                    description={description_clone}
+                    ui=ui
+                    repository=config.html_config().git_repository_url
+                    repository_icon=config.html_config().git_repository_icon
+                />
+            }.into_view(),
+        }}
         // Async is a component from the async_component module.
         // It will wrap an async function that returns an IntoView.
         <section class="w-full flex flex-col">
@@ -121,25 +226,80 @@ pub fn Layout(
             // <Async view=navigation_bar />
             {if wide {
                 view!{
-                    <main class="container mx-auto">{children()}</main>
+                    <main id="content" tabindex="-1" class="container mx-auto">{children()}</main>
                 }
             }else {
                 view!{
-                    <main class="">{children()}</main>
+                    <main id="content" tabindex="-1" class="">{children()}</main>
                 }
             }}
         </section>
+        <Footer authors=authors_clone />
+        <AnalyticsScript />
+    }
+}
+
+/// Joins the authors in a human readable way: "A", "A and B", "A, B and C".
+fn join_authors(authors: &[String]) -> Option<String> {
+    match authors {
+        [] => None,
+        [author] => Some(author.clone()),
+        [rest @ .., last] => Some(format!("{} and {last}", rest.join(", "))),
     }
 }
 
 #[component]
-pub fn Header(#[prop(into)] title: String, #[prop(into)] description: String) -> impl IntoView {
+pub fn Footer(#[prop(into)] authors: Vec<String>) -> impl IntoView {
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    let footer = site.footer;
+
+    if let Some(template) = site.theme.footer {
+        let authors = join_authors(&authors).unwrap_or_default();
+        let html = fill(&template, &[("authors", authors.as_str())]);
+        return view! { <div inner_html=html /> }.into_view();
+    }
+
+    view! {
+        <footer class="sidebar-offset px-6 py-4 text-sm">
+            {footer.map(|footer| view! { <div inner_html=footer /> })}
+            {join_authors(&authors).map(|authors| view! { <p>{authors}</p> })}
+        </footer>
+    }
+    .into_view()
+}
+
+/// Collapses the sidebar from the button of [`Header`], remembering it for the next pages.
+const TOGGLE_SIDEBAR_SCRIPT: &str = "
+document.querySelectorAll('button[aria-controls=\"sidebar\"]').forEach((button) => {
+    const collapsed = () => document.documentElement.classList.contains('sidebar-collapsed');
+    button.setAttribute('aria-expanded', String(!collapsed()));
+    button.addEventListener('click', () => {
+        document.documentElement.classList.toggle('sidebar-collapsed');
+        button.setAttribute('aria-expanded', String(!collapsed()));
+        localStorage.setItem('sidebar-collapsed', String(collapsed()));
+    });
+});
+";
+
+#[component]
+pub fn Header(
+    #[prop(into)] title: String,
+    #[prop(into)] description: String,
+    #[prop(optional)] ui: Option<UiStrings>,
+    /// Link to the repository of the book, see `[output.html] git-repository-url`.
+    #[prop(default = None)] repository: Option<String>,
+    #[prop(default = None)] repository_icon: Option<String>,
+) -> impl IntoView {
+    let ui = ui.unwrap_or_else(UiStrings::spanish);
+    let toggle_sidebar = ui.toggle_sidebar.unwrap_or_default();
+    let toggle_theme = ui.toggle_theme.unwrap_or_default();
+
     view! {
-        <div>
-            <nav class="sticky top-0 z-10 flex shadow-md shadow-black p-4 min-h-8 bg-gray-600">
+        <header>
+            <div class="sticky top-0 z-10 flex shadow-md shadow-black p-4 min-h-8 bg-gray-600">
                 <div class="flex flex-wrap justify-between w-full">
                     <div class="items-center flex flex-1 min-w-0">
-                        <button aria-label="Alternar barra lateral" aria-expanded="false" class="hidden mr-2" type="button">
+                        <button aria-label=toggle_sidebar aria-expanded="true" aria-controls="sidebar" class="mr-2" type="button">
                             <svg width="30" height="30" viewBox="0 0 30 30" aria-hidden="true"><path stroke="currentColor" stroke-linecap="round" stroke-miterlimit="10" stroke-width="2" d="M4 7h22M4 15h22M4 23h22"></path></svg>
                         </button>
                         <a class="items-center flex mr-4 min-w-0" href="/rust_book_es/">
@@ -153,12 +313,9 @@ pub fn Header(#[prop(into)] title: String, #[prop(into)] description: String) ->
                         </a>
                     </div>
                     <div class="items-center flex flex-1 min-w-0 flex-shrink-0 flex-grow-0 basis-auto justify-end">
-                        <a href="" target="_blank" rel="noopener noreferrer" class="inline-block p-3">
-                            GitHub
-                            <svg width="13.5" height="13.5" aria-hidden="true" viewBox="0 0 24 24" class="ml-1"><path fill="currentColor" d="M21 13v10h-21v-19h12v2h-10v15h17v-8h2zm3-12h-10.988l4.035 4-6.977 7.07 2.828 2.828 6.977-7.07 4.125 4.172v-11z"></path></svg>
-                        </a>
+                        {repository.map(|url| view! { <RepositoryLink url=url icon=repository_icon /> })}
                         <div class="h-8 w-8">
-                            <button class="items-center rounded-[50%] flex h-full justify-center w-full" type="button" title="Cambiar entre modo oscuro y claro (actualmente modo claro)" aria-label="Cambiar entre modo oscuro y claro (actualmente modo claro)" aria-live="polite">
+                            <button class="items-center rounded-[50%] flex h-full justify-center w-full" type="button" title=toggle_theme.clone() aria-label=toggle_theme aria-live="polite">
                                 <svg viewBox="0 0 24 24" width="24" height="24" class="hidden"><path fill="currentColor" d="M12,9c1.65,0,3,1.35,3,3s-1.35,3-3,3s-3-1.35-3-3S10.35,9,12,9 M12,7c-2.76,0-5,2.24-5,5s2.24,5,5,5s5-2.24,5-5 S14.76,7,12,7L12,7z M2,13l2,0c0.55,0,1-0.45,1-1s-0.45-1-1-1l-2,0c-0.55,0-1,0.45-1,1S1.45,13,2,13z M20,13l2,0c0.55,0,1-0.45,1-1 s-0.45-1-1-1l-2,0c-0.55,0-1,0.45-1,1S19.45,13,20,13z M11,2v2c0,0.55,0.45,1,1,1s1-0.45,1-1V2c0-0.55-0.45-1-1-1S11,1.45,11,2z M11,20v2c0,0.55,0.45,1,1,1s1-0.45,1-1v-2c0-0.55-0.45-1-1-1C11.45,19,11,19.45,11,20z M5.99,4.58c-0.39-0.39-1.03-0.39-1.41,0 c-0.39,0.39-0.39,1.03,0,1.41l1.06,1.06c0.39,0.39,1.03,0.39,1.41,0s0.39-1.03,0-1.41L5.99,4.58z M18.36,16.95 c-0.39-0.39-1.03-0.39-1.41,0c-0.39,0.39-0.39,1.03,0,1.41l1.06,1.06c0.39,0.39,1.03,0.39,1.41,0c0.39-0.39,0.39-1.03,0-1.41 L18.36,16.95z M19.42,5.99c0.39-0.39,0.39-1.03,0-1.41c-0.39-0.39-1.03-0.39-1.41,0l-1.06,1.06c-0.39,0.39-0.39,1.03,0,1.41 s1.03,0.39,1.41,0L19.42,5.99z M7.05,18.36c0.39-0.39,0.39-1.03,0-1.41c-0.39-0.39-1.03-0.39-1.41,0l-1.06,1.06 c-0.39,0.39-0.39,1.03,0,1.41s1.03,0.39,1.41,0L7.05,18.36z"></path></svg><svg viewBox="0 0 24 24" width="24" height="24" class="darkToggleIcon_wfgR"><path fill="currentColor" d="M9.37,5.51C9.19,6.15,9.1,6.82,9.1,7.5c0,4.08,3.32,7.4,7.4,7.4c0.68,0,1.35-0.09,1.99-0.27C17.45,17.19,14.93,19,12,19 c-3.86,0-7-3.14-7-7C5,9.07,6.81,6.55,9.37,5.51z M12,3c-4.97,0-9,4.03-9,9s4.03,9,9,9s9-4.03,9-9c0-0.46-0.04-0.92-0.1-1.36 c-0.98,1.37-2.58,2.26-4.4,2.26c-2.98,0-5.4-2.42-5.4-5.4c0-1.81,0.89-3.42,2.26-4.4C12.92,3.04,12.46,3,12,3L12,3z"></path></svg>
                             </button>
                         </div>
@@ -166,7 +323,10 @@ pub fn Header(#[prop(into)] title: String, #[prop(into)] description: String) ->
                     </div>
                 </div>
                 <div role="presentation" class="navbar-sidebar__backdrop"></div>
-            </nav>
-        </div>
+            </div>
+        </header>
+        <script>
+            {TOGGLE_SIDEBAR_SCRIPT}
+        </script>
     }
 }
@@ -0,0 +1,40 @@
+//! Every link to a generated page is built here, so they all agree on the URL style chosen with
+//! `[output.html] trailing-slash`:
+//!
+//! - unset: `/es/intro.html`, the page is written to `es/intro.html`.
+//! - `false`: `/es/intro`, for hosts serving `intro.html` on extensionless URLs.
+//! - `true`: `/es/intro/`, the page is written to `es/intro/index.html`.
+
+/// Link to `page` (a slug, without extension) relative to its language directory.
+pub fn page_href(page: &str, trailing_slash: Option<bool>) -> String {
+    match trailing_slash {
+        None => format!("{page}.html"),
+        Some(false) => page.to_string(),
+        Some(true) => format!("{page}/"),
+    }
+}
+
+/// Absolute link to `page` of `language`, empty for books without languages.
+pub fn absolute_page_href(language: &str, page: &str, trailing_slash: Option<bool>) -> String {
+    let href = page_href(page, trailing_slash);
+    if language.is_empty() {
+        format!("/{href}")
+    } else {
+        format!("/{language}/{href}")
+    }
+}
+
+/// File `page` is written to, relative to its language directory.
+pub fn page_file(page: &str, trailing_slash: Option<bool>) -> String {
+    match trailing_slash {
+        Some(true) => format!("{page}/index.html"),
+        _ => format!("{page}.html"),
+    }
+}
+
+/// Inverse of [`page_file`], the page written to `file`.
+pub fn page_from_file(file: &str) -> &str {
+    file.strip_suffix("/index.html")
+        .or_else(|| file.strip_suffix(".html"))
+        .unwrap_or(file)
+}
@@ -22,6 +22,13 @@ pub fn Head(children: Children) -> impl IntoView {
     ctx.head_els.borrow_mut().push(children());
 }
 
+/// Appends trusted, unescaped markup to the `<head>` of the page.
+#[component]
+pub fn RawHead(#[prop(into)] html: String) -> impl IntoView {
+    let ctx = expect_context::<ShellCtx>();
+    ctx.raw_head_els.borrow_mut().push(html);
+}
+
 #[component(transparent)]
 pub fn Dedup(#[prop(into)] key: String, children: Children) -> impl IntoView {
     let ctx = expect_context::<ShellCtx>();
@@ -36,6 +43,7 @@ pub fn Dedup(#[prop(into)] key: String, children: Children) -> impl IntoView {
 pub struct ShellCtx {
     head_els: Rc<RefCell<Vec<Fragment>>>,
     deduped_head_els: Rc<RefCell<HashMap<String, Fragment>>>,
+    raw_head_els: Rc<RefCell<Vec<String>>>,
     html_attrs: Rc<RefCell<Attrs>>,
     body_attrs: Rc<RefCell<Attrs>>,
 }
@@ -56,9 +64,10 @@ impl ShellCtx {
         });
 
         format!(
-            "<!DOCTYPE html><html {}><head>{}</head><body {}>{}</body></html>",
+            "<!DOCTYPE html><html {}><head>{}{}</head><body {}>{}</body></html>",
             self.html_attrs.borrow().render(),
             head,
+            self.raw_head_els.borrow().concat(),
             self.body_attrs.borrow().render(),
             inner_body.trim(),
         )
@@ -1,6 +1,14 @@
+pub mod analytics;
 pub mod chapterpage;
+pub mod errorpage;
 pub mod homepage;
 pub mod layout;
+pub mod links;
 pub mod meta;
+pub mod overrides;
+pub mod pagination;
+pub mod printpage;
+pub mod repository;
+pub mod structured_data;
 pub mod custom_component;
 pub mod chapters_navigator;
\ No newline at end of file
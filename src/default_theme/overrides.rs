@@ -0,0 +1,88 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use crate::renderer::markdown::escape_attr;
+
+/// Files of the book's `theme/` directory replacing their bundled equivalent.
+#[derive(Debug, Clone, Default)]
+pub struct ThemeOverrides {
+    /// `style.css`, written instead of the bundled stylesheet.
+    pub css: Option<String>,
+    /// `head.html`, appended to the `<head>` of every page.
+    pub head: Option<String>,
+    /// `header.html`, replaces the header. `{title}` and `{description}` are substituted.
+    pub header: Option<String>,
+    /// `footer.html`, replaces the footer. `{authors}` is substituted.
+    pub footer: Option<String>,
+    /// `chapter.html`, replaces the whole page of the chapters.
+    pub chapter: Option<String>,
+    /// `chapter_body.html`, replaces the content of the pages beside the sidebar.
+    pub chapter_body: Option<String>,
+    /// `chapter_navigator.html`, replaces the sidebar. `{links}` is substituted.
+    pub chapter_navigator: Option<String>,
+    /// `chapter_navigator_item.html`, replaces each link of the sidebar. `{link}` and `{title}`
+    /// are substituted.
+    pub chapter_navigator_item: Option<String>,
+}
+
+impl ThemeOverrides {
+    /// Loads the overrides present in `theme_dir`, missing files keep the bundled version.
+    pub fn load(theme_dir: &Path) -> Self {
+        let read = |name: &str| {
+            let content = read_to_string(theme_dir.join(name)).ok();
+            if content.is_some() {
+                log::info!("Using {name} from {}", theme_dir.display());
+            }
+            content
+        };
+
+        Self {
+            css: read("style.css"),
+            head: read("head.html"),
+            header: read("header.html"),
+            footer: read("footer.html"),
+            chapter: read("chapter.html"),
+            chapter_body: read("chapter_body.html"),
+            chapter_navigator: read("chapter_navigator.html"),
+            chapter_navigator_item: read("chapter_navigator_item.html"),
+        }
+    }
+}
+
+/// Replaces every `{key}` in `template` with its value. The values are text, like titles, they're
+/// escaped so they can't break the markup of the template.
+pub fn fill(template: &str, props: &[(&str, &str)]) -> String {
+    props.iter().fold(template.to_string(), |content, (key, value)| {
+        content.replace(&format!("{{{key}}}"), &escape_attr(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_templates_come_from_the_theme_directory() {
+        let theme_dir = tempfile::tempdir().unwrap();
+        std::fs::write(theme_dir.path().join("chapter_navigator.html"), "<ul>{links}</ul>").unwrap();
+
+        let theme = ThemeOverrides::load(theme_dir.path());
+
+        assert_eq!(theme.chapter_navigator.as_deref(), Some("<ul>{links}</ul>"));
+        assert_eq!(theme.chapter_navigator_item, None);
+        assert_eq!(theme.chapter, None);
+    }
+
+    #[test]
+    fn filled_values_are_escaped() {
+        let html = fill(
+            r#"<h1 title="{title}">{title}</h1>"#,
+            &[("title", r#"<script>"Tom" & 'Jerry'</script>"#)],
+        );
+
+        assert_eq!(
+            html,
+            "<h1 title=\"&lt;script&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/script&gt;\">&lt;script&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/script&gt;</h1>"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use leptos::{component, use_context, view, IntoView};
+
+use crate::default_theme::links::absolute_page_href;
+use crate::models::lang_config::UiStrings;
+use crate::models::Chapter;
+use crate::renderer::ssg::SiteContext;
+
+/// Links to the chapters before and after `current`, skipping the ones hidden from the
+/// navigation.
+#[component]
+pub fn Pagination(
+    #[prop(into)] chapters: Arc<[Chapter]>,
+    #[prop(into)] current: String,
+    #[prop(into)] language: String,
+) -> impl IntoView {
+    let ui = use_context::<UiStrings>().unwrap_or_else(UiStrings::spanish);
+    let site = use_context::<SiteContext>().unwrap_or_default();
+    let chapters = chapters.iter().filter(|chapter| chapter.in_toc()).collect::<Vec<_>>();
+    let position = chapters
+        .iter()
+        .position(|chapter| chapter.slug.as_deref() == Some(current.as_str()));
+    let Some(position) = position else {
+        return ().into_view();
+    };
+
+    let link = |chapter: &Chapter| {
+        absolute_page_href(
+            site.language_dir(&language),
+            chapter.slug.as_deref().unwrap_or_default(),
+            site.trailing_slash,
+        )
+    };
+    let previous = position.checked_sub(1).and_then(|i| chapters.get(i)).map(|&chapter| {
+        view! {
+            <a rel="prev" href=link(chapter) class="mr-auto">
+                {format!("← {}", ui.previous.clone().unwrap_or_default())}
+            </a>
+        }
+    });
+    let next = chapters.get(position + 1).map(|&chapter| {
+        view! {
+            <a rel="next" href=link(chapter) class="ml-auto">
+                {format!("{} →", ui.next.clone().unwrap_or_default())}
+            </a>
+        }
+    });
+
+    view! {
+        <nav aria-label=ui.chapter_navigation.clone() class="flex w-full py-6">
+            {previous}
+            {next}
+        </nav>
+    }
+    .into_view()
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use leptos::{component, view, CollectView, IntoView};
+
+use crate::default_theme::{chapterpage::MarkdownRender, layout::Layout};
+use crate::models::Chapter;
+
+/// Every chapter of a language in a single page, meant to be printed.
+/// Anchors are prefixed with the chapter slug so they stay unique across the page.
+#[component]
+pub fn PrintPage(
+    #[prop(into)] chapters: Arc<[Chapter]>,
+    #[prop()] language: String,
+    #[prop(default = true)] page_break: bool,
+) -> impl IntoView {
+    view! {
+        <Layout is_home=false wide=false language=language>
+            <div class="px-6">
+                {chapters.iter().map(|chapter| {
+                    let slug = chapter.slug.clone().unwrap_or_default();
+                    let content = chapter.content.clone().unwrap_or_default();
+                    let number = chapter.number.as_ref().map(ToString::to_string);
+
+                    view! {
+                        <section
+                            id=slug.clone()
+                            class="markdown-container prose dark:prose-invert max-w-none"
+                            class:print-page-break=page_break
+                        >
+                            <MarkdownRender content=content id_prefix=slug number=number />
+                        </section>
+                    }
+                }).collect_view()}
+            </div>
+        </Layout>
+    }
+}
@@ -0,0 +1,69 @@
+use leptos::{component, view, IntoView};
+
+/// Hosts with their own icon, anything else gets a generic git icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Forge {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Git,
+}
+
+impl Forge {
+    /// The icon configured with `git-repository-icon`, mdBook's Font Awesome names like
+    /// `fa-github` included, else the one of the host of `url`.
+    fn detect(url: &str, icon: Option<&str>) -> Self {
+        let name = match icon {
+            Some(icon) => icon.trim_start_matches("fa-").to_ascii_lowercase(),
+            None => url.to_ascii_lowercase(),
+        };
+
+        if name.contains("github") {
+            Forge::GitHub
+        } else if name.contains("gitlab") {
+            Forge::GitLab
+        } else if name.contains("bitbucket") {
+            Forge::Bitbucket
+        } else {
+            Forge::Git
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Forge::GitHub => "GitHub",
+            Forge::GitLab => "GitLab",
+            Forge::Bitbucket => "Bitbucket",
+            Forge::Git => "Git repository",
+        }
+    }
+
+    /// Path of the icon, drawn in a 16x16 box.
+    fn icon(self) -> &'static str {
+        match self {
+            Forge::GitHub => "M8 0C3.58 0 0 3.58 0 8c0 3.54 2.29 6.53 5.47 7.59.4.07.55-.17.55-.38 0-.19-.01-.82-.01-1.49-2.01.37-2.53-.49-2.69-.94-.09-.23-.48-.94-.82-1.13-.28-.15-.68-.52-.01-.53.63-.01 1.08.58 1.23.82.72 1.21 1.87.87 2.33.66.07-.52.28-.87.51-1.07-1.78-.2-3.64-.89-3.64-3.95 0-.87.31-1.59.82-2.15-.08-.2-.36-1.02.08-2.12 0 0 .67-.21 2.2.82.64-.18 1.32-.27 2-.27.68 0 1.36.09 2 .27 1.53-1.04 2.2-.82 2.2-.82.44 1.1.16 1.92.08 2.12.51.56.82 1.27.82 2.15 0 3.07-1.87 3.75-3.65 3.95.29.25.54.73.54 1.48 0 1.07-.01 1.93-.01 2.2 0 .21.15.46.55.38A8.013 8.013 0 0016 8c0-4.42-3.58-8-8-8z",
+            Forge::GitLab => "M8 15.2 1 9.9l1.6-7.1 2 5.4h6.8l2-5.4 1.6 7.1z",
+            Forge::Bitbucket => "M1 1.5h14L13 14.5H3zm5.2 4.3.6 4.4h2.4l.6-4.4z",
+            Forge::Git => "M15.7 7.3 8.7.3a1 1 0 0 0-1.4 0L5.8 1.8l1.8 1.8a1.2 1.2 0 0 1 1.5 1.5l1.8 1.8a1.2 1.2 0 1 1-.7.7L8.5 5.9v4.3a1.2 1.2 0 1 1-1-.1V5.8a1.2 1.2 0 0 1-.6-1.6L5.1 2.5.3 7.3a1 1 0 0 0 0 1.4l7 7a1 1 0 0 0 1.4 0l7-7a1 1 0 0 0 0-1.4z",
+        }
+    }
+}
+
+/// Icon linking to the repository of the book, see `[output.html] git-repository-url`.
+#[component]
+pub fn RepositoryLink(
+    #[prop(into)] url: String,
+    /// `git-repository-icon`, chosen from the URL when missing.
+    #[prop(default = None)]
+    icon: Option<String>,
+) -> impl IntoView {
+    let forge = Forge::detect(&url, icon.as_deref());
+
+    view! {
+        <a href=url target="_blank" rel="noopener noreferrer" class="inline-block p-3" title=forge.name() aria-label=forge.name()>
+            <svg width="20" height="20" viewBox="0 0 16 16" aria-hidden="true">
+                <path fill="currentColor" d=forge.icon()></path>
+            </svg>
+        </a>
+    }
+}
@@ -0,0 +1,109 @@
+//! JSON-LD describing the pages to search engines: a `Book` for the homepage and a
+//! `TechArticle` with breadcrumbs for the chapters, see <https://schema.org>.
+
+use futures::executor::block_on;
+use leptos::{component, use_context, view, IntoView};
+use serde_json::{json, Map, Value};
+
+use crate::commands::CONFIG;
+use crate::default_theme::links::absolute_page_href;
+use crate::default_theme::meta::RawHead;
+use crate::models::{Chapter, Config};
+use crate::renderer::ssg::SiteContext;
+
+/// Adds the JSON-LD of the page to its `<head>`, nothing when the book has no title or authors.
+#[component]
+pub fn StructuredData(
+    #[prop(default = None)] chapter: Option<Chapter>,
+    #[prop(into)] language: String,
+) -> impl IntoView {
+    let config = block_on(CONFIG.read()).clone().unwrap_or_default();
+    let site = use_context::<SiteContext>().unwrap_or_default();
+
+    json_ld(&config, chapter.as_ref(), &language, &site).map(|data| {
+        // `</script>` inside a string would end the script element
+        let data = data.to_string().replace("</", "<\\/");
+        let html = format!(r#"<script type="application/ld+json">{data}</script>"#);
+        view! { <RawHead html=html /> }
+    })
+}
+
+fn json_ld(
+    config: &Config,
+    chapter: Option<&Chapter>,
+    language: &str,
+    site: &SiteContext,
+) -> Option<Value> {
+    let index = (!language.is_empty()).then_some(language);
+    let title = config.get_localized_title(index).filter(|title| !title.is_empty())?;
+    let authors = config.get_localized_authors(index);
+    if authors.is_empty() {
+        return None;
+    }
+    let authors = authors
+        .iter()
+        .map(|name| json!({ "@type": "Person", "name": name }))
+        .collect::<Vec<_>>();
+    let site_url = config.html_config().site_url;
+    let site_url = site_url.as_deref().map(|url| url.trim_end_matches('/'));
+    let language = match index {
+        Some(language) => Some(language.to_string()),
+        None => config.default_language(),
+    };
+
+    let mut book = Map::new();
+    book.insert("@type".into(), "Book".into());
+    book.insert("name".into(), title.clone().into());
+    if let Some(url) = site_url {
+        book.insert("url".into(), format!("{url}/").into());
+    }
+
+    let Some(chapter) = chapter.filter(|chapter| !chapter.title.is_empty()) else {
+        book.insert("@context".into(), "https://schema.org".into());
+        book.insert("author".into(), authors.into());
+        if let Some(description) = config.get_localized_description(index) {
+            book.insert("description".into(), description.into());
+        }
+        if let Some(language) = language {
+            book.insert("inLanguage".into(), language.into());
+        }
+        return Some(book.into());
+    };
+
+    let mut article = Map::new();
+    article.insert("@context".into(), "https://schema.org".into());
+    article.insert("@type".into(), "TechArticle".into());
+    article.insert("headline".into(), chapter.title.clone().into());
+    article.insert("author".into(), authors.into());
+    article.insert("isPartOf".into(), book.into());
+    if let Some(language) = &language {
+        article.insert("inLanguage".into(), language.clone().into());
+    }
+    if let Some(date) = &chapter.date {
+        article.insert("datePublished".into(), date.clone().into());
+    }
+    if let Some(date) = chapter.updated.as_ref().or(chapter.date.as_ref()) {
+        article.insert("dateModified".into(), date.clone().into());
+    }
+
+    // Breadcrumbs need absolute URLs
+    if let (Some(url), Some(slug)) = (site_url, &chapter.slug) {
+        let page_url = format!(
+            "{url}{}",
+            absolute_page_href(site.language_dir(index.unwrap_or_default()), slug, site.trailing_slash)
+        );
+        article.insert("url".into(), page_url.clone().into());
+        article.insert(
+            "breadcrumb".into(),
+            json!({
+                "@type": "BreadcrumbList",
+                "itemListElement": [
+                    { "@type": "ListItem", "position": 1, "name": title, "item": format!("{url}/") },
+                    { "@type": "ListItem", "position": 2, "name": chapter.numbered_title(), "item": page_url },
+                ],
+            }),
+        );
+    }
+
+    Some(article.into())
+}
@@ -0,0 +1,124 @@
+//! Warnings and errors about the book, printed for humans or as newline-delimited JSON for
+//! editors, see `--message-format`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+static FORMAT: OnceCell<MessageFormat> = OnceCell::new();
+static DENY_WARNINGS: AtomicBool = AtomicBool::new(false);
+static DENIED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// Readable text on stderr
+    #[default]
+    Human,
+    /// One JSON object per line on stdout
+    Json,
+}
+
+/// Sets how the diagnostics are printed, only the first call has effect.
+pub fn set_format(format: MessageFormat) {
+    _ = FORMAT.set(format);
+}
+
+pub fn format() -> MessageFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Reports the warnings as errors from now on, see `--deny-warnings`.
+pub fn deny_warnings() {
+    DENY_WARNINGS.store(true, Ordering::Relaxed);
+}
+
+/// How many warnings were reported as errors, the command fails when there's any.
+pub fn denied_warnings() -> usize {
+    DENIED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A problem found in the book, optionally pointing to the file and line causing it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            file: None,
+            line: None,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    pub fn file(mut self, file: impl AsRef<Path>) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    /// Prints the diagnostic in the format chosen with [`set_format`].
+    pub fn emit(&self) {
+        if self.severity == Severity::Warning && DENY_WARNINGS.load(Ordering::Relaxed) {
+            DENIED.fetch_add(1, Ordering::Relaxed);
+            return Diagnostic { severity: Severity::Error, ..self.clone() }.emit();
+        }
+
+        match format() {
+            MessageFormat::Human => eprintln!("{self}"),
+            MessageFormat::Json => match serde_json::to_string(self) {
+                Ok(json) => println!("{json}"),
+                Err(error) => log::error!("Unable to serialize diagnostic: {error}"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{line}: ", file.display())?,
+            (Some(file), None) => write!(f, "{}: ", file.display())?,
+            _ => {}
+        }
+        f.write_str(&self.message)
+    }
+}
@@ -0,0 +1,165 @@
+//! Errors of the library API, [`Book::load`] and [`build_book`], for the programs embedding
+//! mdbook-killer that need to tell the failures apart. The commands keep reporting `anyhow`
+//! errors, these are converted from them at the boundary.
+//!
+//! [`Book::load`]: crate::models::book::Book::load
+//! [`build_book`]: crate::commands::build_book
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::models::BookNotFound;
+
+/// Why a book couldn't be loaded or built.
+#[derive(Debug)]
+pub enum BuildError {
+    /// The configuration file doesn't exist.
+    ConfigNotFound(PathBuf),
+    /// The configuration file isn't valid TOML or describes an unusable book, like an invalid
+    /// `date-format`.
+    ConfigParse {
+        path: PathBuf,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The frontmatter of a chapter can't be parsed.
+    ChapterParse {
+        path: PathBuf,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// Reading the configuration file or the sources, like a missing `book.src`, or writing the
+    /// output failed. The message has the file involved, the kind is the one of the original
+    /// error.
+    Io(io::Error),
+    /// Any other failure of the build.
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl BuildError {
+    /// The error of reading the configuration file at `path`.
+    pub(crate) fn config(path: PathBuf, error: anyhow::Error) -> Self {
+        if error.is::<BookNotFound>() {
+            return BuildError::ConfigNotFound(path);
+        }
+        if let Some(io) = io_error(&error) {
+            return BuildError::Io(io);
+        }
+
+        BuildError::ConfigParse {
+            path,
+            source: error.into(),
+        }
+    }
+
+    /// The error of generating the page `page` of the output, an [`io::Error`] when it couldn't
+    /// be written.
+    pub(crate) fn page(page: &str, error: Box<dyn Error>) -> Self {
+        let message = format!("Unable to generate {page}: {error}");
+        match error.downcast_ref::<io::Error>() {
+            Some(io) => BuildError::Io(io::Error::new(io.kind(), message)),
+            None => BuildError::Other(message.into()),
+        }
+    }
+}
+
+/// The [`io::Error`] at the root of `error`, with the message of the whole chain.
+fn io_error(error: &anyhow::Error) -> Option<io::Error> {
+    let io = error.root_cause().downcast_ref::<io::Error>()?;
+    Some(io::Error::new(io.kind(), format!("{error:#}")))
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::ConfigNotFound(path) => {
+                write!(f, "{}", BookNotFound(path.clone()))
+            }
+            BuildError::ConfigParse { path, source } => {
+                write!(f, "Invalid configuration file {}: {source}", path.display())
+            }
+            BuildError::ChapterParse { path, source } => {
+                write!(f, "Unable to parse the chapter {}: {source}", path.display())
+            }
+            BuildError::Io(error) => error.fmt(f),
+            BuildError::Other(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::ConfigNotFound(_) => None,
+            BuildError::ConfigParse { source, .. } | BuildError::ChapterParse { source, .. } => {
+                Some(source.as_ref())
+            }
+            // Transparent, their message is already the one of the error
+            BuildError::Io(error) => error.source(),
+            BuildError::Other(error) => error.source(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for BuildError {
+    /// Recovers the [`BuildError`] raised inside the build, or else sorts the error by its root
+    /// cause.
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<BuildError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        if let Some(io) = io_error(&error) {
+            return BuildError::Io(io);
+        }
+        if let Some(not_found) = error.downcast_ref::<BookNotFound>() {
+            return BuildError::ConfigNotFound(not_found.0.clone());
+        }
+
+        BuildError::Other(error.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn configuration_errors_are_sorted_by_their_cause() {
+        let path = PathBuf::from("book.toml");
+
+        let not_found = BuildError::config(path.clone(), BookNotFound(path.clone()).into());
+        assert!(matches!(not_found, BuildError::ConfigNotFound(_)));
+
+        let denied = Err::<(), _>(io::Error::from(io::ErrorKind::PermissionDenied))
+            .context("Unable to open the configuration file")
+            .unwrap_err();
+        match BuildError::config(path.clone(), denied) {
+            BuildError::Io(error) => {
+                assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+                assert!(error.to_string().starts_with("Unable to open the configuration file: "));
+            }
+            error => panic!("expected an I/O error, got {error:?}"),
+        }
+
+        let invalid = anyhow::anyhow!("Invalid configuration file");
+        assert!(matches!(BuildError::config(path, invalid), BuildError::ConfigParse { .. }));
+    }
+
+    #[test]
+    fn page_errors_keep_the_kind_of_the_failed_write() {
+        let denied = Box::new(io::Error::from(io::ErrorKind::PermissionDenied));
+        match BuildError::page("print.html", denied) {
+            BuildError::Io(error) => {
+                assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+                assert!(error.to_string().starts_with("Unable to generate print.html: "));
+            }
+            error => panic!("expected an I/O error, got {error:?}"),
+        }
+
+        let invalid = BuildError::page("index.html", "invalid page".into());
+        assert_eq!(invalid.to_string(), "Unable to generate index.html: invalid page");
+    }
+}
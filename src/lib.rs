@@ -1,5 +1,8 @@
 pub mod cli;
 pub mod commands;
 pub mod default_theme;
+pub mod diagnostic;
+pub mod error;
 pub mod models;
+pub mod preprocessors;
 pub mod renderer;
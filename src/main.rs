@@ -1,12 +1,32 @@
-use anyhow::Result;
+use std::process::ExitCode;
 
-use mdbook_killer::cli::get_cli;
-use mdbook_killer::models::Config;
+use mdbook_killer::cli::{exit_code, get_cli};
+use mdbook_killer::commands::bundled_css;
+use mdbook_killer::diagnostic::Diagnostic;
+use mdbook_killer::models::BookNotFound;
 
 #[tokio::main]
-pub async fn main() -> Result<()> {
+pub async fn main() -> ExitCode {
+    std::panic::set_hook(Box::new(|info| {
+        eprintln!("Error: mdbook-killer crashed, please report this bug. {info}");
+    }));
+
     let cli = get_cli();
+    let Some(commands) = &cli.commands else {
+        // `get_cli` only lets `--print-theme` through without a command
+        print!("{}", bundled_css());
+        return ExitCode::from(exit_code::SUCCESS);
+    };
 
-    cli.commands.execute().await?;
-    Ok(())
+    match commands.execute(cli.config_file()).await {
+        Ok(()) => ExitCode::from(exit_code::SUCCESS),
+        Err(error) => {
+            Diagnostic::error(format!("{error:#}")).emit();
+            if error.is::<BookNotFound>() {
+                ExitCode::from(exit_code::BOOK_NOT_FOUND)
+            } else {
+                ExitCode::from(exit_code::FAILURE)
+            }
+        }
+    }
 }
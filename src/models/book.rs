@@ -0,0 +1,736 @@
+//! A book loaded from disk, its configuration and the chapters of every language.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use gray_matter::engine::YAML;
+use gray_matter::Matter;
+
+use crate::diagnostic::Diagnostic;
+use crate::default_theme::links::page_href;
+use crate::error::BuildError;
+use crate::renderer::markdown::{first_heading, headings, retarget_anchor_links, slugify};
+
+use self::summary::Entry;
+use super::build_config::LandingPage;
+use super::preprocessors_config::AnchorStyle;
+use super::{Chapter, Config};
+
+mod summary;
+
+/// Source of the not-found page, unless `[output.html] input-404` names another file.
+pub const NOT_FOUND_FILE: &str = "404.md";
+/// Source of the page shown by the service worker when a page isn't cached and there's no network.
+pub const OFFLINE_FILE: &str = "offline.md";
+
+/// Slugs of the pages generated next to the chapters, a chapter with one of them is renamed so
+/// it doesn't overwrite the page or get overwritten by it.
+pub const RESERVED_SLUGS: [&str; 6] = ["index", "404", "offline", "print", "search", "style"];
+
+/// Everything read from the disk to build a book, before any preprocessing.
+#[derive(Debug, Clone)]
+pub struct Book {
+    pub config: Config,
+    /// Root directory of the book, files outside of it are never read.
+    pub root: PathBuf,
+    /// The chapters of each language, in the order of `book.languages`. A book without
+    /// languages has a single section with an empty language.
+    pub sections: Vec<LanguageSection>,
+}
+
+/// The chapters written in one language, in order.
+#[derive(Debug, Clone, Default)]
+pub struct LanguageSection {
+    pub language: String,
+    pub chapters: Vec<Chapter>,
+    /// What `build.landing-page` names the landing page of the folder.
+    pub landing_page: LandingPage,
+}
+
+impl LanguageSection {
+    /// The chapter read from the `index.md` or `README.md` of the language folder.
+    pub fn landing_page(&self) -> Option<&Chapter> {
+        landing_chapter(&self.chapters, self.landing_page)
+    }
+}
+
+/// The chapter of `chapters` read from the landing page of its folder, see `build.landing-page`.
+pub fn landing_chapter(chapters: &[Chapter], landing_page: LandingPage) -> Option<&Chapter> {
+    let is_file = |chapter: &&Chapter, name: &str| {
+        chapter
+            .path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .and_then(|file| file.to_str())
+            .is_some_and(|file| file.eq_ignore_ascii_case(name))
+    };
+    let [winner, loser] = landing_page.precedence();
+
+    chapters
+        .iter()
+        .find(|chapter| is_file(chapter, winner))
+        .or_else(|| chapters.iter().find(|chapter| is_file(chapter, loser)))
+}
+
+impl Book {
+    /// Loads the `book.toml` of `root` and the chapters of every language. Unlike for the
+    /// commands, a chapter that can't be parsed is an error instead of being left out.
+    pub fn load(root: &Path) -> Result<Book, BuildError> {
+        let config_file = root.join("book.toml");
+        let config = Config::from_disk(&config_file)
+            .map_err(|error| BuildError::config(config_file, error))?;
+        Ok(Book::read(config, root, true)?)
+    }
+
+    /// Loads the chapters of every language of an already read `config`.
+    pub(crate) fn from_config(config: Config, root: &Path) -> Result<Book> {
+        Book::read(config, root, false)
+    }
+
+    fn read(config: Config, root: &Path, strict: bool) -> Result<Book> {
+        let languages = config.book.languages.clone().unwrap_or_else(|| vec![String::new()]);
+        let landing_page = config.build.clone().unwrap_or_default().landing_page;
+        let html = config.html_config();
+        let trailing_slash = html.trailing_slash;
+        let anchor_style = html.anchor_style.unwrap_or_default();
+
+        let sections = languages
+            .into_iter()
+            .map(|language| {
+                let folder = config.book.src.join(&language);
+                log::debug!("Reading in {:?}", folder);
+                let chapters = charpters_from_folder(
+                    &folder,
+                    root,
+                    landing_page,
+                    trailing_slash,
+                    anchor_style,
+                    strict,
+                )?;
+                Ok(LanguageSection {
+                    language,
+                    chapters,
+                    landing_page,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Book {
+            config,
+            root: root.to_path_buf(),
+            sections,
+        })
+    }
+
+    /// The section of `language`, `""` for a book without languages.
+    pub fn section(&self, language: &str) -> Option<&LanguageSection> {
+        self.sections.iter().find(|section| section.language == language)
+    }
+}
+
+/// Appends `-page` to the slug of `chapter` when it's reserved for a generated page, warning
+/// about it unless the chapter is an `index.md` landing page.
+fn avoid_reserved_slug(chapter: &mut Chapter, path: &Path) {
+    let Some(slug) = chapter.slug.as_mut() else {
+        return;
+    };
+    if !RESERVED_SLUGS.iter().any(|reserved| slug.eq_ignore_ascii_case(reserved)) {
+        return;
+    }
+
+    let renamed = format!("{slug}-page");
+    // `index.md` is the landing page of its folder, renaming it is expected
+    let is_landing_page = path
+        .file_name()
+        .and_then(|file| file.to_str())
+        .is_some_and(|file| file.eq_ignore_ascii_case("index.md"));
+    if is_landing_page {
+        *slug = renamed;
+        return;
+    }
+    Diagnostic::warning(format!(
+        "the slug `{slug}` is reserved for generated pages ({}), the chapter is written as `{renamed}`",
+        RESERVED_SLUGS.join(", ")
+    ))
+    .file(path)
+    .emit();
+    *slug = renamed;
+}
+
+/// Whether `path`, once its symlinks are resolved, is inside `root`.
+/// Warns about the paths escaping it.
+pub(crate) fn within_root(path: &Path, root: &Path) -> bool {
+    let inside = match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) => path.starts_with(root),
+        _ => false,
+    };
+    if !inside {
+        Diagnostic::warning(format!(
+            "skipped, it points outside of the book root {}",
+            root.display()
+        ))
+        .file(path)
+        .emit();
+    }
+    inside
+}
+
+/// Moves the landing page of a folder, `index.md` or `README.md`, before the other chapters.
+/// When the folder has both, the one chosen by `landing_page` wins and the other is skipped.
+fn landing_page_first(paths: &mut Vec<PathBuf>, landing_page: LandingPage) {
+    let find = |paths: &[PathBuf], name: &str| {
+        paths.iter().position(|path| {
+            path.file_name()
+                .and_then(|file| file.to_str())
+                .is_some_and(|file| file.eq_ignore_ascii_case(name))
+        })
+    };
+    let [winner, loser] = landing_page.precedence();
+
+    if let (Some(_), Some(position)) = (find(paths, winner), find(paths, loser)) {
+        let skipped = paths.remove(position);
+        Diagnostic::warning(format!(
+            "skipped, {winner} is the landing page of the folder, see `build.landing-page`"
+        ))
+        .file(skipped)
+        .emit();
+    }
+    if let Some(position) = find(paths, winner).or_else(|| find(paths, loser)) {
+        let landing = paths.remove(position);
+        paths.insert(0, landing);
+    }
+}
+
+/// The chapter written in the markdown `content` of the file `path`.
+///
+/// With a frontmatter, between `---` lines, the chapter is read from it. Without a `title`
+/// there, the first `# Heading` or else the slug titles the chapter, with a warning. Without a
+/// frontmatter the title is the first line with text, a thematic break at the start of the file
+/// isn't a frontmatter when it isn't closed. The slug is the file name unless the frontmatter
+/// sets one.
+pub fn parse_chapter(path: &Path, content: &str) -> Result<Chapter> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let file = path
+        .file_stem()
+        .and_then(|file| file.to_str())
+        .with_context(|| "Could not convert path to str")?;
+
+    let mut chapter = if has_frontmatter(content) {
+        let parsed = Matter::<YAML>::new()
+            .parse_with_struct::<Chapter>(content)
+            .ok_or_else(|| anyhow!("unable to parse the frontmatter"))?;
+        let mut chapter: Chapter = parsed.data;
+        chapter.content = Some(parsed.content);
+        if chapter.title.trim().is_empty() {
+            let slug = chapter.slug.as_deref().unwrap_or(file);
+            chapter.title = chapter
+                .content
+                .as_deref()
+                .and_then(first_heading)
+                .unwrap_or_else(|| slug.to_string());
+            Diagnostic::warning(format!(
+                "the frontmatter has no `title`, the chapter is titled `{}`",
+                chapter.title
+            ))
+            .file(path)
+            .line(1)
+            .emit();
+        }
+        chapter
+    } else {
+        let title = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !is_thematic_break(line))
+            .ok_or_else(|| anyhow!("the chapter is empty, it has no title"))?;
+        Chapter {
+            title: title.to_string(),
+            content: Some(content.to_string()),
+            ..Default::default()
+        }
+    };
+    chapter.slug.get_or_insert_with(|| file.to_string());
+    chapter.path = Some(path.to_path_buf());
+
+    Ok(chapter)
+}
+
+/// Cuts `chapter` at its headings of the `split-at` level, each one starting a chapter titled
+/// and slugged after it. The content before the first of them stays in `chapter`, which takes
+/// the first section when there's none, so the links to the file still work. The links to
+/// anchors that ended up in another chapter are pointed at its page.
+///
+/// The new chapters keep the frontmatter of the file, but its `summary`, and get slugs not in
+/// `used_slugs`, the ones of the language section so far, which they're added to.
+fn split_chapter(
+    mut chapter: Chapter,
+    trailing_slash: Option<bool>,
+    anchor_style: AnchorStyle,
+    used_slugs: &mut HashSet<String>,
+) -> Vec<Chapter> {
+    used_slugs.insert(chapter.slug.clone().unwrap_or_default());
+    let Some(split_at) = chapter.split_at else {
+        return vec![chapter];
+    };
+    let Some(content) = chapter.content.take() else {
+        return vec![chapter];
+    };
+    let all_headings = headings(&content, anchor_style);
+    let cuts = all_headings
+        .iter()
+        .filter(|heading| heading.level == split_at.level())
+        .collect::<Vec<_>>();
+    if cuts.is_empty() {
+        chapter.content = Some(content);
+        return vec![chapter];
+    }
+
+    let keeps_preamble = !content[..cuts[0].start].trim().is_empty();
+    let mut chapters = Vec::with_capacity(cuts.len() + 1);
+    if keeps_preamble {
+        chapter.content = Some(content[..cuts[0].start].to_string());
+        chapters.push(chapter.clone());
+    }
+    for (index, cut) in cuts.iter().enumerate() {
+        let end = cuts.get(index + 1).map_or(content.len(), |next| next.start);
+        let section = content[cut.start..end].to_string();
+        if index == 0 && !keeps_preamble {
+            chapter.content = Some(section);
+            chapters.push(chapter.clone());
+            continue;
+        }
+
+        // Pages are slugged the same whatever the anchor style, changing it doesn't move them
+        let base = match slugify(&cut.text, AnchorStyle::Mdbook) {
+            slug if slug.is_empty() => format!("{}-{index}", chapter.slug.as_deref().unwrap_or_default()),
+            slug => slug,
+        };
+        let mut slug = base.clone();
+        let mut suffix = 1;
+        while !used_slugs.insert(slug.clone()) {
+            slug = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        chapters.push(Chapter {
+            title: cut.text.clone(),
+            content: Some(section),
+            slug: Some(slug),
+            // The excerpt of the file isn't the one of its sections
+            summary: None,
+            ..chapter.clone()
+        });
+    }
+
+    // Every anchor to the chapter it's in now
+    let offset = if keeps_preamble { 0 } else { 1 };
+    let page_of = |start: usize| {
+        let cut = cuts.iter().rposition(|cut| cut.start <= start);
+        match cut {
+            Some(cut) => cut + 1 - offset,
+            None => 0,
+        }
+    };
+    let anchors = all_headings
+        .iter()
+        .map(|heading| (heading.id.as_str(), page_of(heading.start)))
+        .collect::<HashMap<_, _>>();
+    let pages = chapters
+        .iter()
+        .map(|chapter| page_href(chapter.slug.as_deref().unwrap_or_default(), trailing_slash))
+        .collect::<Vec<_>>();
+    for (page, chapter) in chapters.iter_mut().enumerate() {
+        let Some(content) = &chapter.content else {
+            continue;
+        };
+        let retargeted = retarget_anchor_links(content, |anchor| {
+            let target = *anchors.get(anchor)?;
+            (target != page).then(|| format!("{}#{anchor}", pages[target]))
+        });
+        chapter.content = Some(retargeted);
+    }
+
+    chapters
+}
+
+/// Appends the `parts` of `chapter` to its content, in order and without their frontmatter,
+/// returning where they are. A missing part is an error.
+///
+/// A heading of a part with the anchor of an earlier one gets a suffixed anchor on the page, the
+/// links of the part to it are changed to follow.
+fn assemble_parts(
+    chapter: &mut Chapter,
+    root: &Path,
+    anchor_style: AnchorStyle,
+) -> Result<Vec<PathBuf>> {
+    let (Some(parts), Some(path)) = (&chapter.parts, &chapter.path) else {
+        return Ok(Vec::new());
+    };
+    let folder = path.parent().unwrap_or(Path::new("."));
+
+    let mut files = Vec::with_capacity(parts.len());
+    let mut content = chapter.content.clone().unwrap_or_default();
+    for part in parts {
+        let file = folder.join(part);
+        let markdown = fs::read_to_string(&file).with_context(|| {
+            format!("Unable to read {}, a part of the chapter `{}`", file.display(), chapter.title)
+        })?;
+        let canonical = file.canonicalize()?;
+        if !root.canonicalize().is_ok_and(|root| canonical.starts_with(root)) {
+            bail!("{}, a part of the chapter `{}`, is outside of the book", file.display(), chapter.title);
+        }
+        let markdown = markdown.strip_prefix('\u{feff}').unwrap_or(&markdown);
+        let markdown = if has_frontmatter(markdown) {
+            Matter::<YAML>::new().parse(markdown).content
+        } else {
+            markdown.to_string()
+        };
+
+        content.push_str("\n\n");
+        let start = content.len();
+        content.push_str(&markdown);
+        let renamed = renamed_anchors(&content, start, &markdown, anchor_style);
+        if !renamed.is_empty() {
+            let retargeted = retarget_anchor_links(&markdown, |anchor| {
+                renamed.get(anchor).map(|id| format!("#{id}"))
+            });
+            content.replace_range(start.., &retargeted);
+        }
+        files.push(canonical);
+    }
+    chapter.content = Some(content);
+
+    Ok(files)
+}
+
+/// The anchors of the headings of `part` that change once it's at `start` of `content`, taken by
+/// a heading before it.
+fn renamed_anchors(
+    content: &str,
+    start: usize,
+    part: &str,
+    anchor_style: AnchorStyle,
+) -> HashMap<String, String> {
+    let own = headings(part, anchor_style);
+    let assembled = headings(content, anchor_style);
+    let assembled = assembled.iter().filter(|heading| heading.start >= start);
+
+    own.iter()
+        .zip(assembled)
+        .filter(|(own, assembled)| own.id != assembled.id)
+        .map(|(own, assembled)| (own.id.clone(), assembled.id.clone()))
+        .collect()
+}
+
+/// Whether `content` starts with a frontmatter, a `---` line closed by another one.
+fn has_frontmatter(content: &str) -> bool {
+    let mut lines = content.lines();
+    lines.next().is_some_and(|line| line.trim_end() == "---")
+        && lines.any(|line| line.trim_end() == "---")
+}
+
+/// Whether `line` is a markdown thematic break, like `---` or `* * *`.
+fn is_thematic_break(line: &str) -> bool {
+    ['-', '*', '_'].iter().any(|&mark| {
+        line.chars().filter(|char| *char == mark).count() >= 3
+            && line.chars().all(|char| char == mark || char == ' ')
+    })
+}
+
+/// Chapters of `folder` in the order of its `SUMMARY.md`, or sorted by file name without one,
+/// the landing page first.
+///
+/// A chapter that can't be parsed is reported and left out, or with `strict` is a
+/// [`BuildError::ChapterParse`].
+pub(crate) fn charpters_from_folder(
+    folder: &Path,
+    root: &Path,
+    landing_page: LandingPage,
+    trailing_slash: Option<bool>,
+    anchor_style: AnchorStyle,
+    strict: bool,
+) -> Result<Vec<Chapter>> {
+    let mut chapters = Vec::with_capacity(10);
+    let mut part_files = HashSet::new();
+    let mut used_slugs = HashSet::new();
+
+    let entries = match summary::chapter_files(folder, landing_page)? {
+        Some(entries) => entries,
+        None => {
+            let chapter_folder = fs::read_dir(folder)
+                .with_context(|| format!("Unable to read {}", folder.display()))?;
+            // `read_dir` doesn't guarantee any order, sort by file name so the output is stable
+            let mut paths = chapter_folder
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            // The not-found and offline pages aren't chapters
+            paths.retain(|path| {
+                !path.file_name().is_some_and(|name| name == NOT_FOUND_FILE || name == OFFLINE_FILE)
+            });
+            paths.sort();
+            landing_page_first(&mut paths, landing_page);
+            paths
+                .into_iter()
+                .map(|path| Entry::File {
+                    path,
+                    depth: 0,
+                    slug: None,
+                })
+                .collect()
+        }
+    };
+
+    for entry in entries {
+        let (file, depth, slug) = match entry {
+            Entry::File { path, depth, slug } => (path, depth, slug),
+            Entry::Group { title, slug, depth } => {
+                used_slugs.insert(slug.clone());
+                chapters.push(Chapter {
+                    content: Some(format!("# {title}\n")),
+                    title,
+                    slug: Some(slug),
+                    depth,
+                    ..Default::default()
+                });
+                continue;
+            }
+        };
+        if !file.is_file() || file.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        if !within_root(&file, root) {
+            continue;
+        }
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Unable to read {}", file.display()))?;
+        match parse_chapter(&file, &content) {
+            Ok(mut chapter) => {
+                // The slug of a glob match, unless the frontmatter sets one
+                let stem = file.file_stem().and_then(|stem| stem.to_str());
+                if slug.is_some() && chapter.slug.as_deref() == stem {
+                    chapter.slug = slug;
+                }
+                part_files.extend(assemble_parts(&mut chapter, root, anchor_style)?);
+                let split = split_chapter(chapter, trailing_slash, anchor_style, &mut used_slugs);
+                for mut chapter in split {
+                    avoid_reserved_slug(&mut chapter, &file);
+                    chapter.depth = depth;
+                    chapters.push(chapter);
+                }
+            }
+            Err(error) if strict => {
+                return Err(BuildError::ChapterParse {
+                    path: file,
+                    source: error.into(),
+                }
+                .into());
+            }
+            Err(error) => Diagnostic::error(format!("{error:#}")).file(&file).line(1).emit(),
+        }
+    }
+    // Without a `SUMMARY.md` the parts are found in the folder too
+    chapters.retain(|chapter| {
+        let path = chapter.path.as_ref().and_then(|path| path.canonicalize().ok());
+        !path.is_some_and(|path| part_files.contains(&path))
+    });
+    // A file can still take the slug of a section split from a file before it
+    let mut slugs = HashSet::new();
+    for chapter in &chapters {
+        let slug = chapter.slug.as_deref().unwrap_or_default();
+        if !slugs.insert(slug) {
+            let mut diagnostic = Diagnostic::warning(format!(
+                "Two chapters have the slug `{slug}`, one page overwrites the other, rename the \
+                 file or set its `slug` in the frontmatter"
+            ));
+            if let Some(path) = &chapter.path {
+                diagnostic = diagnostic.file(path);
+            }
+            diagnostic.emit();
+        }
+    }
+
+    Ok(chapters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<Chapter> {
+        parse_chapter(Path::new("src/intro.md"), content)
+    }
+
+    #[test]
+    fn title_is_the_first_line() {
+        let chapter = parse("# Introduction\n\nSome text.\n").unwrap();
+
+        assert_eq!(chapter.title, "# Introduction");
+        assert_eq!(chapter.slug.as_deref(), Some("intro"));
+        assert_eq!(chapter.content.as_deref(), Some("# Introduction\n\nSome text.\n"));
+        assert_eq!(chapter.path.as_deref(), Some(Path::new("src/intro.md")));
+    }
+
+    #[test]
+    fn byte_order_mark_is_skipped() {
+        let chapter = parse("\u{feff}---\ntitle: Introduction\n---\nSome text.\n").unwrap();
+
+        assert_eq!(chapter.title, "Introduction");
+    }
+
+    #[test]
+    fn empty_file_is_an_error() {
+        assert!(parse("").is_err());
+        assert!(parse("\n  \n").is_err());
+    }
+
+    #[test]
+    fn frontmatter_only() {
+        let chapter = parse("---\ntitle: Introduction\nslug: start\n---\n").unwrap();
+
+        assert_eq!(chapter.title, "Introduction");
+        assert_eq!(chapter.slug.as_deref(), Some("start"));
+        assert_eq!(chapter.content.as_deref().map(str::trim), Some(""));
+    }
+
+    #[test]
+    fn frontmatter_without_title_uses_the_first_heading() {
+        let chapter = parse("---\ndescription: No title\n---\nSome text.\n\n# Introduction\n").unwrap();
+
+        assert_eq!(chapter.title, "Introduction");
+    }
+
+    #[test]
+    fn frontmatter_without_title_nor_heading_uses_the_slug() {
+        let chapter = parse("---\nslug: start\n---\nSome text.\n").unwrap();
+
+        assert_eq!(chapter.title, "start");
+    }
+
+    #[test]
+    fn thematic_break_at_start_is_not_a_frontmatter() {
+        let chapter = parse("---\n\nIntroduction\n\nSome text.\n").unwrap();
+
+        assert_eq!(chapter.title, "Introduction");
+        assert_eq!(chapter.content.as_deref(), Some("---\n\nIntroduction\n\nSome text.\n"));
+    }
+
+    #[test]
+    fn split_at_cuts_a_chapter_per_heading() {
+        let chapter = parse(
+            "---\ntitle: Guide\nsplit-at: h1\n---\nIntro, see [setup](#setup).\n\n# Install\n\nSee [usage](#usage).\n\n## Setup\n\n# Usage\n\nBack to [install](#install).\n",
+        )
+        .unwrap();
+        let chapters = split_chapter(chapter, None, AnchorStyle::Mdbook, &mut HashSet::new());
+
+        let slugs = chapters.iter().map(|chapter| chapter.slug.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(slugs, ["intro", "install", "usage"]);
+        assert_eq!(chapters[1].title, "Install");
+        let contents = chapters.iter().map(|chapter| chapter.content.as_deref().unwrap().trim()).collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            [
+                "Intro, see [setup](install.html#setup).",
+                "# Install\n\nSee [usage](usage.html#usage).\n\n## Setup",
+                "# Usage\n\nBack to [install](install.html#install).",
+            ]
+        );
+    }
+
+    #[test]
+    fn chapters_without_split_at_keep_their_content() {
+        let chapter = parse("---\ntitle: Guide\n---\n# Install\n\n# Usage\n").unwrap();
+        let chapters = split_chapter(chapter, None, AnchorStyle::Mdbook, &mut HashSet::new());
+
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].content.as_deref(), Some("# Install\n\n# Usage"));
+    }
+
+    #[test]
+    fn split_at_without_preamble_keeps_the_file_slug_for_the_first_section() {
+        let chapter = parse("---\nsplit-at: h2\n---\n## One\n\n## One\n").unwrap();
+        let chapters = split_chapter(chapter, Some(true), AnchorStyle::Mdbook, &mut HashSet::new());
+
+        let slugs = chapters.iter().map(|chapter| chapter.slug.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(slugs, ["intro", "one"]);
+    }
+
+    #[test]
+    fn split_chapters_keep_the_frontmatter_and_avoid_the_slugs_of_the_section() {
+        let chapter = parse(
+            "---\nsplit-at: h1\ndescription: About\ncss: [demo.css]\nsummary: Short\n---\n\
+             # Install\n\n# Usage\n",
+        )
+        .unwrap();
+        let mut used_slugs = HashSet::from(["usage".to_string()]);
+        let chapters = split_chapter(chapter, None, AnchorStyle::Mdbook, &mut used_slugs);
+
+        assert_eq!(chapters[1].slug.as_deref(), Some("usage-1"));
+        assert_eq!(chapters[1].description.as_deref(), Some("About"));
+        assert_eq!(chapters[1].css, Some(vec![PathBuf::from("demo.css")]));
+        assert_eq!(chapters[1].summary, None);
+        assert!(used_slugs.contains("usage-1"));
+    }
+
+    /// A chapter of `root` with `parts`, after writing `files` there.
+    fn chapter_with_parts(root: &Path, parts: &[&str], files: &[(&str, &str)]) -> Chapter {
+        for (file, content) in files {
+            fs::write(root.join(file), content).unwrap();
+        }
+        let path = root.join("chapter.md");
+        fs::write(&path, "---\ntitle: Guide\n---\n# Guide\n").unwrap();
+        let mut chapter = parse_chapter(&path, &fs::read_to_string(&path).unwrap()).unwrap();
+        chapter.parts = Some(parts.iter().map(PathBuf::from).collect());
+        chapter
+    }
+
+    #[test]
+    fn parts_are_appended_in_order_without_their_frontmatter() {
+        let root = tempfile::tempdir().unwrap();
+        let files = [("b.md", "---\ntitle: B\n---\nSecond\n"), ("a.md", "\u{feff}Third\n")];
+        let mut chapter = chapter_with_parts(root.path(), &["b.md", "a.md"], &files);
+
+        let parts = assemble_parts(&mut chapter, root.path(), AnchorStyle::Mdbook).unwrap();
+
+        let content = chapter.content.unwrap();
+        let (second, third) = (content.find("Second").unwrap(), content.find("Third").unwrap());
+        assert!(content.starts_with("# Guide") && second < third);
+        assert!(!content.contains("title: B") && !content.contains('\u{feff}'));
+        let names = parts.iter().map(|part| part.file_name().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, ["b.md", "a.md"]);
+    }
+
+    #[test]
+    fn missing_parts_and_parts_outside_the_book_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("book");
+        fs::create_dir(&root).unwrap();
+        fs::write(dir.path().join("outside.md"), "Outside\n").unwrap();
+
+        let mut chapter = chapter_with_parts(&root, &["missing.md"], &[]);
+        let error = assemble_parts(&mut chapter, &root, AnchorStyle::Mdbook).unwrap_err();
+        assert!(error.to_string().contains("a part of the chapter `Guide`"));
+
+        let mut chapter = chapter_with_parts(&root, &["../outside.md"], &[]);
+        let error = assemble_parts(&mut chapter, &root, AnchorStyle::Mdbook).unwrap_err();
+        assert!(error.to_string().ends_with("is outside of the book"));
+    }
+
+    #[test]
+    fn links_of_a_part_follow_its_renamed_headings() {
+        let root = tempfile::tempdir().unwrap();
+        let files = [
+            ("one.md", "## Setup\n\nSee [setup](#setup).\n"),
+            ("two.md", "## Setup\n\nSee [setup](#setup) and [the guide](#guide).\n"),
+        ];
+        let mut chapter = chapter_with_parts(root.path(), &["one.md", "two.md"], &files);
+
+        assemble_parts(&mut chapter, root.path(), AnchorStyle::Mdbook).unwrap();
+
+        let content = chapter.content.unwrap();
+        assert!(content.contains("## Setup\n\nSee [setup](#setup).\n"));
+        assert!(content.contains("See [setup](#setup-1) and [the guide](#guide)."));
+        let ids = headings(&content, AnchorStyle::Mdbook).into_iter().map(|heading| heading.id);
+        assert_eq!(ids.collect::<Vec<_>>(), ["guide", "setup", "setup-1"]);
+    }
+}
@@ -0,0 +1,381 @@
+//! `SUMMARY.md` lists the chapters of a language in order, every `[Title](file.md)` link of
+//! its list items is a chapter. A link to a glob, like `[Recipes](recipes/*.md)`, stands for
+//! all the matching files sorted by path, nested under the item.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use globset::GlobBuilder;
+use ignore::WalkBuilder;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use crate::diagnostic::Diagnostic;
+use crate::models::build_config::LandingPage;
+use crate::models::preprocessors_config::AnchorStyle;
+use crate::renderer::markdown::slugify;
+
+pub const FILE: &str = "SUMMARY.md";
+
+/// A chapter listed by `SUMMARY.md`, with its nesting in the list, 0 for the top level.
+#[derive(Debug, PartialEq)]
+pub enum Entry {
+    /// A markdown file. The files matched by a glob are slugged after their path, so
+    /// `recipes/a/intro.md` and `recipes/b/intro.md` don't take the same page.
+    File {
+        path: PathBuf,
+        depth: usize,
+        slug: Option<String>,
+    },
+    /// The item of a glob without a landing page among its matches, a page titled after its
+    /// link that the matches are nested under.
+    Group {
+        title: String,
+        slug: String,
+        depth: usize,
+    },
+}
+
+/// A link of a list item.
+#[derive(Debug, PartialEq)]
+struct Link {
+    title: String,
+    target: String,
+    depth: usize,
+}
+
+/// Chapters listed by the `SUMMARY.md` of `folder`, `None` when there's no summary.
+///
+/// The files matched by a glob are nested under its item: the landing page of the folder of the
+/// glob, `index.md` or `README.md` as chosen by `landing_page`, takes the place of the item, or
+/// else a page titled after it does.
+pub fn chapter_files(folder: &Path, landing_page: LandingPage) -> Result<Option<Vec<Entry>>> {
+    let summary_path = folder.join(FILE);
+    if !summary_path.is_file() {
+        return Ok(None);
+    }
+    let summary = fs::read_to_string(&summary_path)
+        .with_context(|| format!("Unable to read {}", summary_path.display()))?;
+
+    let mut entries = Vec::new();
+    let links = links(&summary).with_context(|| format!("Invalid {}", summary_path.display()))?;
+    for Link {
+        title,
+        target,
+        depth,
+    } in links
+    {
+        if target.contains(['*', '?', '[']) {
+            entries.extend(glob_entries(folder, &title, &target, depth, landing_page)?);
+            continue;
+        }
+
+        let path = folder.join(&target);
+        if path.is_file() {
+            entries.push(Entry::File {
+                path,
+                depth,
+                slug: None,
+            });
+        } else {
+            Diagnostic::warning(format!("`{target}` is listed but doesn't exist"))
+                .file(&summary_path)
+                .emit();
+        }
+    }
+
+    Ok(Some(entries))
+}
+
+/// The item `[title](pattern)` at `depth`, followed by the files matching `pattern` one level
+/// deeper.
+fn glob_entries(
+    folder: &Path,
+    title: &str,
+    pattern: &str,
+    depth: usize,
+    landing_page: LandingPage,
+) -> Result<Vec<Entry>> {
+    let mut files = expand(folder, pattern)?;
+    let base = glob_base(pattern);
+    let base_slug = path_slug(Path::new(base));
+
+    let landing = landing_position(folder, &files, base, landing_page);
+    let group = match landing {
+        Some(position) => Entry::File {
+            path: files.remove(position),
+            depth,
+            slug: Some(base_slug),
+        },
+        None if base_slug.is_empty() => Entry::Group {
+            title: title.to_string(),
+            slug: slugify(title, AnchorStyle::Mdbook),
+            depth,
+        },
+        None => Entry::Group {
+            title: title.to_string(),
+            slug: base_slug,
+            depth,
+        },
+    };
+
+    let mut entries = vec![group];
+    entries.extend(files.into_iter().map(|path| {
+        let relative = path.strip_prefix(folder).unwrap_or(&path).with_extension("");
+        Entry::File {
+            slug: Some(path_slug(&relative)),
+            path,
+            depth: depth + 1,
+        }
+    }));
+    Ok(entries)
+}
+
+/// Position in `files` of the landing page of the folder `base`, relative to `folder`. The
+/// root folder has none, its landing page is the homepage.
+fn landing_position(
+    folder: &Path,
+    files: &[PathBuf],
+    base: &str,
+    landing_page: LandingPage,
+) -> Option<usize> {
+    if base.is_empty() {
+        return None;
+    }
+    landing_page.precedence().iter().find_map(|name| {
+        files.iter().position(|file| {
+            let Ok(relative) = file.strip_prefix(folder) else {
+                return false;
+            };
+            relative.parent() == Some(Path::new(base))
+                && relative
+                    .file_name()
+                    .and_then(|file| file.to_str())
+                    .is_some_and(|file| file.eq_ignore_ascii_case(name))
+        })
+    })
+}
+
+/// The folder of the files `pattern` matches, its components before the first one with a
+/// wildcard, like `recipes` for `recipes/**/*.md`.
+fn glob_base(pattern: &str) -> &str {
+    let wildcard = pattern.find(['*', '?', '[']).unwrap_or(pattern.len());
+    pattern[..wildcard].rfind('/').map_or("", |end| &pattern[..end])
+}
+
+/// Slug of the relative `path`, its components joined by dashes.
+fn path_slug(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The links of the list items, in order, with the depth of their item, 0 for the top level.
+///
+/// A chapter nested under itself, like `a.md` listed inside the item of `a.md`, is an error with
+/// the path from the outermost one, the navigation tree would never end.
+fn links(summary: &str) -> Result<Vec<Link>> {
+    let mut depth = 0;
+    let mut links = Vec::new();
+    // Chapters of the items enclosing the current one, with their depth
+    let mut ancestors: Vec<(usize, String)> = Vec::new();
+    // The link being read, its title is the text until its end
+    let mut link: Option<Link> = None;
+
+    for event in Parser::new(summary) {
+        match event {
+            Event::Start(Tag::Item) => depth += 1,
+            Event::End(TagEnd::Item) => {
+                ancestors.retain(|(ancestor_depth, _)| *ancestor_depth < depth);
+                depth -= 1;
+            }
+            Event::Start(Tag::Link { dest_url, .. }) if depth > 0 && !dest_url.is_empty() => {
+                let target = dest_url.to_string();
+                if let Some(position) = ancestors.iter().position(|(_, ancestor)| *ancestor == target) {
+                    let cycle = ancestors[position..]
+                        .iter()
+                        .map(|(_, ancestor)| ancestor.as_str())
+                        .chain([target.as_str()])
+                        .collect::<Vec<_>>();
+                    bail!("`{target}` is nested under itself: {}", cycle.join(" -> "));
+                }
+                // Only the first link of an item is its chapter
+                if ancestors.last().is_none_or(|(ancestor_depth, _)| *ancestor_depth < depth) {
+                    ancestors.push((depth, target.clone()));
+                }
+                link = Some(Link {
+                    title: String::new(),
+                    target,
+                    depth: depth - 1,
+                });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(link) = &mut link {
+                    link.title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => links.extend(link.take()),
+            _ => {}
+        }
+    }
+
+    Ok(links)
+}
+
+/// Files of `folder` matching the `pattern` glob, sorted so the chapter order is stable.
+fn expand(folder: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("Invalid glob `{pattern}` in {FILE}"))?
+        .compile_matcher();
+
+    let mut files = WalkBuilder::new(folder)
+        .standard_filters(false)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.strip_prefix(folder).is_ok_and(|relative| matcher.is_match(relative)))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(title: &str, target: &str, depth: usize) -> Link {
+        Link {
+            title: title.to_string(),
+            target: target.to_string(),
+            depth,
+        }
+    }
+
+    fn touch(folder: &Path, files: &[&str]) {
+        for file in files {
+            let path = folder.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "# Chapter\n").unwrap();
+        }
+    }
+
+    #[test]
+    fn links_of_the_list_items_with_their_depth() {
+        let summary = "# Summary\n\n[Outside](outside.md)\n\n\
+                       - [Intro](intro.md)\n  - [The `setup`](setup.md) and [more](more.md)\n\
+                       - [Draft]()\n- [Recipes](recipes/*.md)\n";
+
+        assert_eq!(
+            links(summary).unwrap(),
+            [
+                link("Intro", "intro.md", 0),
+                link("The setup", "setup.md", 1),
+                link("more", "more.md", 1),
+                link("Recipes", "recipes/*.md", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_chapter_nested_under_itself_is_an_error() {
+        let summary = "- [A](a.md)\n  - [B](b.md)\n    - [A again](a.md)\n";
+        let error = links(summary).unwrap_err();
+
+        assert_eq!(error.to_string(), "`a.md` is nested under itself: a.md -> b.md -> a.md");
+        // Siblings aren't nested
+        assert!(links("- [A](a.md)\n- [A again](a.md)\n").is_ok());
+    }
+
+    #[test]
+    fn globs_match_sorted_files_without_crossing_folders() {
+        let folder = tempfile::tempdir().unwrap();
+        let folder = folder.path();
+        touch(folder, &["recipes/b.md", "recipes/a.md", "recipes/deep/c.md", "other.md"]);
+
+        let relative = |pattern| {
+            expand(folder, pattern)
+                .unwrap()
+                .into_iter()
+                .map(|path| path.strip_prefix(folder).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            relative("recipes/*.md"),
+            [Path::new("recipes/a.md"), Path::new("recipes/b.md")]
+        );
+        assert_eq!(relative("recipes/**/*.md").len(), 3);
+        assert!(expand(folder, "recipes/[.md").is_err());
+    }
+
+    #[test]
+    fn glob_matches_are_nested_under_their_item() {
+        let folder = tempfile::tempdir().unwrap();
+        let folder = folder.path();
+        touch(folder, &["intro.md", "recipes/a/intro.md", "recipes/b/intro.md"]);
+        let summary = "- [Intro](intro.md)\n- [Recipes](recipes/**/*.md)\n";
+        fs::write(folder.join(FILE), summary).unwrap();
+
+        let entries = chapter_files(folder, LandingPage::Index).unwrap().unwrap();
+        assert_eq!(
+            entries,
+            [
+                Entry::File {
+                    path: folder.join("intro.md"),
+                    depth: 0,
+                    slug: None,
+                },
+                Entry::Group {
+                    title: "Recipes".to_string(),
+                    slug: "recipes".to_string(),
+                    depth: 0,
+                },
+                Entry::File {
+                    path: folder.join("recipes/a/intro.md"),
+                    depth: 1,
+                    slug: Some("recipes-a-intro".to_string()),
+                },
+                Entry::File {
+                    path: folder.join("recipes/b/intro.md"),
+                    depth: 1,
+                    slug: Some("recipes-b-intro".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn the_landing_page_of_the_glob_folder_takes_the_place_of_the_item() {
+        let folder = tempfile::tempdir().unwrap();
+        let folder = folder.path();
+        touch(folder, &["recipes/README.md", "recipes/pasta.md"]);
+        fs::write(folder.join(FILE), "- [Recipes](recipes/*.md)\n").unwrap();
+
+        let entries = chapter_files(folder, LandingPage::Readme).unwrap().unwrap();
+        assert_eq!(
+            entries,
+            [
+                Entry::File {
+                    path: folder.join("recipes/README.md"),
+                    depth: 0,
+                    slug: Some("recipes".to_string()),
+                },
+                Entry::File {
+                    path: folder.join("recipes/pasta.md"),
+                    depth: 1,
+                    slug: Some("recipes-pasta".to_string()),
+                },
+            ]
+        );
+        assert_eq!(glob_base("*.md"), "");
+        assert_eq!(glob_base("a/b/c*/*.md"), "a/b");
+    }
+}
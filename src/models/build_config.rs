@@ -16,6 +16,47 @@ pub struct BuildConfig {
     pub use_default_preprocessors: bool,
     /// Extra directories to trigger rebuild when watching/serving
     pub extra_watch_dirs: Vec<PathBuf>,
+    /// Assets copied to the output even if the `.gitignore` of the book ignores them,
+    /// relative to the book's source directory.
+    pub force_include: Vec<PathBuf>,
+    /// Which of `index.md` and `README.md` is the landing page of a folder having both.
+    pub landing_page: LandingPage,
+    /// Shell commands run in the book root before the chapters are read, the build stops at
+    /// the first one failing.
+    pub pre_build: Vec<String>,
+    /// Shell commands run in the book root once the book is written.
+    pub post_build: Vec<String>,
+    /// Environment variables the chapters may read with `{{#env NAME}}`, using any other one
+    /// is an error. None by default, so a book can't leak the secrets of the machine building it.
+    pub allowed_env: Vec<String>,
+}
+
+/// Landing page of a folder of chapters, it's shown first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LandingPage {
+    /// `index.md`
+    #[default]
+    Index,
+    /// `README.md`
+    Readme,
+}
+
+impl LandingPage {
+    fn file_name(self) -> &'static str {
+        match self {
+            LandingPage::Index => "index.md",
+            LandingPage::Readme => "README.md",
+        }
+    }
+
+    /// The file of the landing page followed by the one of the other choice.
+    pub fn precedence(self) -> [&'static str; 2] {
+        match self {
+            LandingPage::Index => [self.file_name(), LandingPage::Readme.file_name()],
+            LandingPage::Readme => [self.file_name(), LandingPage::Index.file_name()],
+        }
+    }
 }
 
 impl Default for BuildConfig {
@@ -25,6 +66,11 @@ impl Default for BuildConfig {
             create_missing: true,
             use_default_preprocessors: true,
             extra_watch_dirs: Vec::new(),
+            force_include: Vec::new(),
+            landing_page: LandingPage::default(),
+            pre_build: Vec::new(),
+            post_build: Vec::new(),
+            allowed_env: Vec::new(),
         }
     }
 }
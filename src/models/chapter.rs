@@ -1,8 +1,106 @@
+use std::fmt;
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct Chapter {
+    /// Optional in the frontmatter, the first `# Heading` or else the slug is used without it.
+    #[serde(default)]
     pub title: String,
     pub content: Option<String>,
     pub slug: Option<String>,
+    /// Summary for search engines, the first paragraph of the chapter is used without it.
+    pub description: Option<String>,
+    /// Markdown of the excerpt shown in the chapter listings, instead of the content before
+    /// `<!-- more -->` or the first paragraph.
+    pub summary: Option<String>,
+    /// `toc: false` in the frontmatter builds the chapter but leaves it out of the navigation,
+    /// it's only reachable by direct links.
+    pub toc: Option<bool>,
+    /// Publication date from the frontmatter, like `2024-05-01`.
+    pub date: Option<String>,
+    /// Date of the last significant change from the frontmatter.
+    pub updated: Option<String>,
+    /// `redirect_to` in the frontmatter turns the chapter into a redirect to another page of the
+    /// book or an absolute URL. Its body is ignored and it's left out of the navigation.
+    pub redirect_to: Option<String>,
+    /// Files appended in order to the content of the chapter, relative to it, for a chapter
+    /// split in several files. They aren't chapters themselves.
+    pub parts: Option<Vec<PathBuf>>,
+    /// `split-at: h1` in the frontmatter cuts the file into a chapter per heading of that
+    /// level, titled and slugged after it.
+    #[serde(rename = "split-at", alias = "split_at")]
+    pub split_at: Option<SplitLevel>,
+    /// Stylesheets loaded only by the page of the chapter, relative to it, for a one-off like an
+    /// embedded demo. The book-wide ones are `[output.html] additional-css`.
+    pub css: Option<Vec<PathBuf>>,
+    /// Scripts loaded only by the page of the chapter, relative to it, deferred.
+    pub scripts: Option<Vec<PathBuf>>,
+    /// Position of the chapter in the book, set when `[output.html] number-sections` is enabled.
+    #[serde(skip)]
+    pub number: Option<SectionNumber>,
+    /// HTML of the excerpt shown in the chapter listings, rendered while building.
+    #[serde(skip)]
+    pub excerpt: Option<String>,
+    /// Nesting of the chapter in the list of `SUMMARY.md`, 0 for the top level and for the
+    /// books without one.
+    #[serde(skip)]
+    pub depth: usize,
+    /// Markdown file the chapter was read from.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Links to the `css` of the chapter in the output, set while building.
+    #[serde(skip)]
+    pub css_links: Vec<String>,
+    /// Links to the `scripts` of the chapter in the output, set while building.
+    #[serde(skip)]
+    pub script_links: Vec<String>,
+}
+
+impl Chapter {
+    /// Whether the chapter is listed in the navigation.
+    pub fn in_toc(&self) -> bool {
+        self.toc.unwrap_or(true)
+    }
+
+    /// Title prefixed with the section number, when the chapter has one.
+    pub fn numbered_title(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{number} {}", self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// Position of a chapter in the chapter tree, rendered as `1.2.`.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct SectionNumber(pub Vec<u32>);
+
+impl fmt::Display for SectionNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.0 {
+            write!(f, "{part}.")?;
+        }
+        Ok(())
+    }
+}
+
+/// Heading level `split-at` cuts a file at, `h1` to `h6`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitLevel {
+    H1,
+    H2,
+    H3,
+    H4,
+    H5,
+    H6,
+}
+
+impl SplitLevel {
+    /// 1 for `h1`, up to 6.
+    pub fn level(self) -> u8 {
+        self as u8 + 1
+    }
 }
@@ -17,6 +17,9 @@ pub struct BookConfig {
     pub src: PathBuf,
     /// The main language of the book.
     pub language: Option<String>,
+    /// The languages of the book, in the order they are offered to the readers. The default
+    /// one is the `[language]` marked `default = true`, or else [`BookConfig::language`], or
+    /// else the first one.
     pub languages: Option<Vec<String>>,
     /// The direction of text in the book: Left-to-right (LTR) or Right-to-left (RTL).
     /// When not specified, the text direction is derived from [`BookConfig::language`].
@@ -1,11 +1,10 @@
-use std::collections::HashMap;
-
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-/// Configuration for localizations of this book
+/// Configuration for localizations of this book, in the order of `book.toml`.
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct LanguageConfig(pub HashMap<String, Language>);
+pub struct LanguageConfig(pub IndexMap<String, Language>);
 
 /// Configuration for a single localization
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,4 +18,98 @@ pub struct Language {
     pub authors: Option<Vec<String>>,
     /// Localized description of the book.
     pub description: Option<String>,
+    /// Localized text of the theme's interface.
+    pub ui: Option<UiStrings>,
+    /// Whether this is the default language of the book, instead of `book.language`. Only one
+    /// language can be the default.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+}
+
+/// Text of the theme's interface. Missing strings fall back to the book-wide
+/// `[output.html.ui]` table and then to the Spanish the theme is written in.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct UiStrings {
+    /// Link to the previous chapter.
+    pub previous: Option<String>,
+    /// Link to the next chapter.
+    pub next: Option<String>,
+    /// Label of the button showing and hiding the sidebar.
+    pub toggle_sidebar: Option<String>,
+    /// Label of the button switching between the light and dark themes.
+    pub toggle_theme: Option<String>,
+    /// Label of the reading progress bar.
+    pub reading_progress: Option<String>,
+    /// Link jumping over the header and sidebar, for keyboard and screen reader users.
+    pub skip_to_content: Option<String>,
+    /// Label of the sidebar listing the chapters.
+    pub table_of_contents: Option<String>,
+    /// Label of the links to the previous and next chapters.
+    pub chapter_navigation: Option<String>,
+    /// Button running a code block on the playground.
+    pub run_code: Option<String>,
+    /// Shown instead of the output when the playground can't be reached.
+    pub playground_unavailable: Option<String>,
+    /// Question of the banner asking before loading the analytics script.
+    pub consent_message: Option<String>,
+    /// Button of the consent banner allowing the analytics.
+    pub consent_accept: Option<String>,
+    /// Button of the consent banner refusing the analytics.
+    pub consent_decline: Option<String>,
+    /// Before the publication date of a chapter.
+    pub published: Option<String>,
+    /// Before the date a chapter was last updated.
+    pub last_updated: Option<String>,
+}
+
+impl UiStrings {
+    /// The strings the theme shows without a `ui` table.
+    pub fn spanish() -> Self {
+        Self {
+            previous: Some("Anterior".to_string()),
+            next: Some("Siguiente".to_string()),
+            toggle_sidebar: Some("Alternar barra lateral".to_string()),
+            toggle_theme: Some(
+                "Cambiar entre modo oscuro y claro (actualmente modo claro)".to_string(),
+            ),
+            reading_progress: Some("Progreso de lectura".to_string()),
+            skip_to_content: Some("Saltar al contenido".to_string()),
+            table_of_contents: Some("Tabla de contenidos".to_string()),
+            chapter_navigation: Some("Navegación entre capítulos".to_string()),
+            run_code: Some("Ejecutar".to_string()),
+            playground_unavailable: Some(
+                "El playground no está disponible ahora mismo, inténtalo más tarde.".to_string(),
+            ),
+            consent_message: Some(
+                "Este sitio quiere contar las visitas con analíticas respetuosas con la privacidad."
+                    .to_string(),
+            ),
+            consent_accept: Some("Aceptar".to_string()),
+            consent_decline: Some("Rechazar".to_string()),
+            published: Some("Publicado".to_string()),
+            last_updated: Some("Última actualización".to_string()),
+        }
+    }
+
+    /// Fills the strings missing in `self` with the ones of `fallback`.
+    pub fn or(self, fallback: UiStrings) -> UiStrings {
+        UiStrings {
+            previous: self.previous.or(fallback.previous),
+            next: self.next.or(fallback.next),
+            toggle_sidebar: self.toggle_sidebar.or(fallback.toggle_sidebar),
+            toggle_theme: self.toggle_theme.or(fallback.toggle_theme),
+            reading_progress: self.reading_progress.or(fallback.reading_progress),
+            skip_to_content: self.skip_to_content.or(fallback.skip_to_content),
+            table_of_contents: self.table_of_contents.or(fallback.table_of_contents),
+            chapter_navigation: self.chapter_navigation.or(fallback.chapter_navigation),
+            run_code: self.run_code.or(fallback.run_code),
+            playground_unavailable: self.playground_unavailable.or(fallback.playground_unavailable),
+            consent_message: self.consent_message.or(fallback.consent_message),
+            consent_accept: self.consent_accept.or(fallback.consent_accept),
+            consent_decline: self.consent_decline.or(fallback.consent_decline),
+            published: self.published.or(fallback.published),
+            last_updated: self.last_updated.or(fallback.last_updated),
+        }
+    }
 }
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// CommonMark extensions used to render the chapters, the `[markdown]` table of `book.toml`.
+/// The defaults follow GitHub-flavored markdown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MarkdownConfig {
+    /// `| a | b |` tables.
+    pub tables: bool,
+    /// `[^note]` footnotes.
+    pub footnotes: bool,
+    /// `~~deleted~~` text.
+    pub strikethrough: bool,
+    /// `- [x]` task lists.
+    pub tasklists: bool,
+    /// Curly quotes, dashes and ellipses instead of their ASCII versions.
+    pub smart_punctuation: bool,
+    /// `# Heading {#id .class}` attributes. They aren't part of GitHub-flavored markdown, but
+    /// books written for mdBook rely on them.
+    pub heading_attributes: bool,
+    /// `Term` lines followed by `: definition` lines, rendered as definition lists. Off by
+    /// default, a paragraph line starting with `: ` in an existing book would become a list.
+    pub definition_lists: bool,
+    /// Strips the tags and attributes of raw HTML that aren't in an allowlist of harmless
+    /// ones, for books with chapters from untrusted contributors. Off by default, the HTML of
+    /// trusted books is kept as written.
+    pub sanitize_html: bool,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            smart_punctuation: false,
+            heading_attributes: true,
+            definition_lists: false,
+            sanitize_html: false,
+        }
+    }
+}
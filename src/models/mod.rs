@@ -1,25 +1,30 @@
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::info;
 use serde::{Deserialize, Serialize};
 
 use self::build_config::BuildConfig;
 use self::config_book::BookConfig;
-use self::lang_config::LanguageConfig;
+use self::lang_config::{LanguageConfig, UiStrings};
+use self::markdown_config::MarkdownConfig;
 use self::preprocessors_config::{HtmlPreprocessor, PreprocessorsConfig};
 use self::rust_config::RustConfig;
+use crate::renderer::dates::DateSettings;
 
+pub mod book;
 pub mod build_config;
 mod chapter;
 pub mod config_book;
 pub mod lang_config;
+pub mod markdown_config;
 pub mod preprocessors_config;
 pub mod rust_config;
 
-pub use chapter::Chapter;
+pub use chapter::{Chapter, SectionNumber};
 
 /// The overall configuration object for MDBook, essentially an in-memory
 /// representation of `book.toml`.
@@ -33,19 +38,63 @@ pub struct Config {
     pub rust: RustConfig,
     /// Information about localizations of this book.
     pub language: Option<LanguageConfig>,
+    /// Markdown extensions used to render the chapters.
+    pub markdown: Option<MarkdownConfig>,
     pub output: Option<PreprocessorsConfig>,
 }
 
+/// The configuration file of the book doesn't exist.
+#[derive(Debug)]
+pub struct BookNotFound(pub PathBuf);
+
+impl fmt::Display for BookNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Couldn't find a book, {} doesn't exist", self.0.display())
+    }
+}
+
+impl std::error::Error for BookNotFound {}
+
 impl Config {
     /// Load the configuration file from disk.
+    ///
+    /// `book.src` is resolved to an absolute path from the directory of the configuration file,
+    /// which must exist and be a directory.
     pub fn from_disk<P: AsRef<Path>>(config_file: P) -> Result<Config> {
+        let config_file = config_file.as_ref();
+        if !config_file.exists() {
+            return Err(BookNotFound(config_file.to_path_buf()).into());
+        }
+
         let mut buffer = String::new();
         File::open(config_file)
             .with_context(|| "Unable to open the configuration file")?
             .read_to_string(&mut buffer)
             .with_context(|| "Couldn't read the file")?;
 
-        toml::from_str(&buffer).with_context(|| "Invalid configuration file")
+        let mut config: Config =
+            toml::from_str(&buffer).with_context(|| "Invalid configuration file")?;
+        config.book.src = resolve_src(book_root(config_file), &config.book.src)?;
+        // Reported now rather than halfway through the build
+        DateSettings::from_config(&config)?;
+        config.check_default_language()?;
+
+        Ok(config)
+    }
+
+    /// Like [`Config::from_disk`], but a missing configuration file isn't an error: the default
+    /// configuration is used instead, with the directory of the file as the source of a single
+    /// unnamed language. For rendering a folder of markdown without a `book.toml`.
+    pub fn from_disk_or_default<P: AsRef<Path>>(config_file: P) -> Result<Config> {
+        let config_file = config_file.as_ref();
+        if config_file.exists() {
+            return Config::from_disk(config_file);
+        }
+
+        info!("{} doesn't exist, using the default configuration", config_file.display());
+        let mut config = Config::default();
+        config.book.src = resolve_src(book_root(config_file), Path::new("."))?;
+        Ok(config)
     }
 
     /// Convenience method for getting the html renderer's configuration.
@@ -56,7 +105,28 @@ impl Config {
     /// HTML renderer is refactored to be less coupled to `mdbook` internals.
     #[doc(hidden)]
     pub fn html_config(&self) -> HtmlPreprocessor {
-        self.output.clone().unwrap_or_default().html
+        self.output.clone().unwrap_or_default().html.unwrap_or_default()
+    }
+
+    /// Language written to the root of the output instead of its own directory, see
+    /// `[output.html] default-language-in-subdir`.
+    pub fn root_language(&self) -> Option<String> {
+        self.default_language()
+            .filter(|_| !self.html_config().default_language_in_subdir.unwrap_or(true))
+    }
+
+    /// Names of the `[output.*]` sections, HTML first, or just `html` when there's none.
+    pub fn outputs(&self) -> Vec<String> {
+        let output = self.output.clone().unwrap_or_default();
+        let mut others = output.others.into_keys().filter(|name| name != "html").collect::<Vec<_>>();
+        others.sort();
+
+        let mut outputs = Vec::with_capacity(others.len() + 1);
+        if output.html.is_some() || others.is_empty() {
+            outputs.push("html".to_string());
+        }
+        outputs.extend(others);
+        outputs
     }
 
     /// Gets the language configured for a book.
@@ -116,42 +186,47 @@ impl Config {
         }
     }
 
-    /// Gets the localized title of the book.
+    /// Gets the localized title of the book, falling back to [`BookConfig::title`] when the
+    /// language doesn't declare its own or isn't declared at all.
     pub fn get_localized_title<I: AsRef<str>>(&self, index: Option<I>) -> Option<String> {
-        let language = self.get_language(index).unwrap();
+        let language = self.get_language(index).ok().flatten();
 
-        match language {
-            Some(lang_ident) => self
-                .language
-                .clone()
-                .unwrap_or_default()
-                .0
-                .get(&lang_ident)
-                .unwrap()
-                .title
-                .clone()
-                .or(self.book.title.clone()),
-            None => self.book.title.clone(),
-        }
+        language
+            .and_then(|lang_ident| self.language.as_ref()?.0.get(&lang_ident)?.title.clone())
+            .or_else(|| self.book.title.clone())
     }
 
-    /// Gets the localized description of the book.
+    /// Gets the localized description of the book, falling back to
+    /// [`BookConfig::description`] like [`Config::get_localized_title`].
     pub fn get_localized_description<I: AsRef<str>>(&self, index: Option<I>) -> Option<String> {
-        let language = self.get_language(index).unwrap();
+        let language = self.get_language(index).ok().flatten();
 
-        match language {
-            Some(lang_ident) => self
-                .language
-                .clone()
-                .unwrap_or_default()
-                .0
-                .get(&lang_ident)
-                .unwrap()
-                .description
-                .clone()
-                .or(self.book.description.clone()),
-            None => self.book.description.clone(),
-        }
+        language
+            .and_then(|lang_ident| self.language.as_ref()?.0.get(&lang_ident)?.description.clone())
+            .or_else(|| self.book.description.clone())
+    }
+
+    /// Gets the localized authors of the book, falling back to [`BookConfig::authors`]
+    /// when the language doesn't declare its own.
+    pub fn get_localized_authors<I: AsRef<str>>(&self, index: Option<I>) -> Vec<String> {
+        let language = self.get_language(index).ok().flatten();
+
+        language
+            .and_then(|lang_ident| self.language.as_ref()?.0.get(&lang_ident)?.authors.clone())
+            .unwrap_or_else(|| self.book.authors.clone())
+    }
+
+    /// Gets the text of the theme's interface for a language, falling back to the book-wide
+    /// `[output.html.ui]` table and then to the Spanish of the theme.
+    pub fn get_localized_ui<I: AsRef<str>>(&self, index: Option<I>) -> UiStrings {
+        let language = self.get_language(index).ok().flatten();
+        let localized = language
+            .and_then(|lang_ident| self.language.as_ref()?.0.get(&lang_ident)?.ui.clone())
+            .unwrap_or_default();
+
+        localized
+            .or(self.html_config().ui.unwrap_or_default())
+            .or(UiStrings::spanish())
     }
 
     /// Get the fallback source directory of a book. If chapters/sections are
@@ -187,8 +262,12 @@ impl Config {
 
     /// Obtains the default language for this config.
     pub fn default_language(&self) -> Option<String> {
+        if let Some(marked) = self.marked_default_languages().first() {
+            return Some(marked.to_string());
+        }
         if self.has_localized_dir_structure() {
-            let Some(language_ident) = self.book.language.clone() else {
+            let first = self.book.languages.as_ref().and_then(|languages| languages.first());
+            let Some(language_ident) = self.book.language.clone().or_else(|| first.cloned()) else {
                 info!("Config has [language] table, but `book.language` not was declared");
                 return None;
             };
@@ -214,4 +293,107 @@ impl Config {
             None
         }
     }
+
+    /// The `[language]` tables marked `default = true`, in the order of `book.toml`.
+    fn marked_default_languages(&self) -> Vec<&str> {
+        self.language
+            .iter()
+            .flat_map(|languages| &languages.0)
+            .filter(|(_, language)| language.default)
+            .map(|(ident, _)| ident.as_str())
+            .collect()
+    }
+
+    /// Checks the book has exactly one default language, see [`BookConfig::languages`].
+    fn check_default_language(&self) -> Result<()> {
+        let marked = self.marked_default_languages();
+        if marked.len() > 1 {
+            bail!("Only one language can be `default = true`, not {}", marked.join(", "));
+        }
+        if let (Some(marked), Some(main)) = (marked.first(), &self.book.language) {
+            if *marked != main.as_str() {
+                bail!(
+                    "`[language.{marked}]` is the default language, but `book.language` is `{main}`"
+                );
+            }
+        }
+        if let (Some(languages), Some(default)) = (&self.book.languages, self.default_language()) {
+            if !languages.contains(&default) {
+                bail!("The default language `{default}` isn't one of `book.languages`");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Root directory of the book configured by `config_file`, its directory. `book.src`, the theme,
+/// the output and the hooks are relative to it.
+pub fn book_root(config_file: &Path) -> &Path {
+    match config_file.parent() {
+        Some(root) if !root.as_os_str().is_empty() => root,
+        _ => Path::new("."),
+    }
+}
+
+/// Absolute path of the `book.src` directory, relative paths are relative to `root`.
+fn resolve_src(root: &Path, src: &Path) -> Result<PathBuf> {
+    let path = root.join(src);
+    let path = path.canonicalize().with_context(|| {
+        format!("The source directory {} (`book.src`) doesn't exist", path.display())
+    })?;
+    if !path.is_dir() {
+        bail!("The source directory {} (`book.src`) isn't a directory", path.display());
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(&format!("[rust]\n{toml}")).unwrap()
+    }
+
+    #[test]
+    fn the_marked_language_is_the_default() {
+        let config = config(
+            "[book]\nlanguages = [\"en\", \"es\"]\n[language.en]\n[language.es]\ndefault = true\n",
+        );
+
+        assert_eq!(config.default_language().as_deref(), Some("es"));
+        assert!(config.check_default_language().is_ok());
+    }
+
+    #[test]
+    fn there_is_only_one_default_language() {
+        let two_marked =
+            config("[book]\n[language.en]\ndefault = true\n[language.es]\ndefault = true\n");
+        let conflicting = config("[book]\nlanguage = \"en\"\n[language.es]\ndefault = true\n");
+        let undeclared = config("[book]\nlanguage = \"fr\"\nlanguages = [\"en\", \"es\"]\n");
+
+        assert!(two_marked.check_default_language().is_err());
+        assert!(conflicting.check_default_language().is_err());
+        assert!(undeclared.check_default_language().is_err());
+    }
+
+    #[test]
+    fn undeclared_languages_fall_back_to_the_book() {
+        let config = config(
+            "[book]\ntitle = \"Book\"\ndescription = \"About\"\nlanguages = [\"es\"]\n\
+             [language.es]\ntitle = \"Libro\"\n",
+        );
+        let unlocalized = Config {
+            language: None,
+            ..config.clone()
+        };
+
+        assert_eq!(config.get_localized_title(Some("es")).as_deref(), Some("Libro"));
+        assert_eq!(config.get_localized_description(Some("es")).as_deref(), Some("About"));
+        assert_eq!(config.get_localized_title(Some("fr")).as_deref(), Some("Book"));
+        assert_eq!(unlocalized.get_localized_title(Some("es")).as_deref(), Some("Book"));
+        assert_eq!(unlocalized.get_localized_description(Some("es")).as_deref(), Some("About"));
+    }
 }
@@ -1,31 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::{Deserialize, Serialize};
 
+use super::lang_config::UiStrings;
+
 /// Configuration for localizations of this book
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PreprocessorsConfig {
-    pub html: HtmlPreprocessor,
+    /// `None` without an `[output.html]` section, the book is then only built in HTML when it
+    /// has no other output.
+    pub html: Option<HtmlPreprocessor>,
     #[serde(flatten)]
     pub others: HashMap<String, Preprocessor>,
 }
 
-///
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct Preprocessor {
-    ///
     pub after: Option<Vec<String>>,
-    ///
     pub before: Option<Vec<String>>,
-    ///
     pub command: Option<String>,
-    ///
     pub renders: Option<Vec<String>>,
     pub optional: Option<bool>,
 }
 
-///
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct HtmlPreprocessor {
@@ -36,29 +34,99 @@ pub struct HtmlPreprocessor {
     pub mathjax_support: Option<bool>,
     pub copy_fonts: Option<bool>,
     pub additional_css: Option<Vec<String>>,
+    /// Whether the bundled `style.css` is written and linked, defaults to `true`.
+    pub default_css: Option<bool>,
     pub additional_js: Option<Vec<String>>,
+    /// Markup added to the `<head>` of every page, like analytics snippets or verification tags.
+    /// Either the HTML itself or the path of a file containing it, relative to the book root.
+    /// It's inserted verbatim, without any escaping, so it must be trusted.
+    pub additional_head: Option<String>,
     pub no_section_label: Option<bool>,
+    /// Prefixes the chapter titles with their section number ("1.", "1.2.").
+    pub number_sections: Option<bool>,
     pub git_repository_url: Option<String>,
     pub git_repository_icon: Option<String>,
     pub edit_url_template: Option<String>,
     pub site_url: Option<String>,
+    /// Style of the links to the generated pages: `page.html` when unset, `page` when `false`
+    /// and `page/` when `true`, writing the pages as `page/index.html`.
+    pub trailing_slash: Option<bool>,
+    /// Whether the default language is written to its own directory, like the other languages,
+    /// with a root page sending the readers to their language. Defaults to `true`, with `false`
+    /// the default language is written to the root.
+    pub default_language_in_subdir: Option<bool>,
     pub cname: Option<String>,
+    /// Markdown file of the source folder rendered as `404.html`, `404.md` by default.
+    /// The built-in not-found page is used when it doesn't exist.
     pub input_404: Option<String>,
+    /// Markdown or HTML rendered at the bottom of every page.
+    /// `{year}` is replaced with the current year at build time.
+    pub footer: Option<String>,
+    /// Adds a `data-source-line` attribute with the markdown line to block elements,
+    /// for editing tools mapping the output back to the source.
+    pub source_lines: Option<bool>,
+    /// Replaces GitHub emoji shortcodes like `:rocket:` with the emoji, except inside code.
+    pub emoji: Option<bool>,
+    /// Adds `loading="lazy"` and `decoding="async"` to the images of the chapters.
+    pub lazy_images: Option<bool>,
+    /// Adds `width` and `height` to the local images of the chapters, avoiding layout shifts.
+    pub image_dimensions: Option<bool>,
+    /// `strftime` format of the dates shown on the pages, like `%B %-d, %Y`, with the month and
+    /// day names in the language of the page. `1 May 2024` by default.
+    pub date_format: Option<String>,
+    /// Timezone the dates with a time are shown in, like `Europe/Paris`. UTC by default.
+    pub timezone: Option<String>,
+    /// How the headings are turned into anchors, see [`AnchorStyle`]. mdBook's by default.
+    pub anchor_style: Option<AnchorStyle>,
+    /// Opens the links of the chapters to other sites in a new tab, with `rel="noopener"`, and
+    /// marks them with an icon. Links to the host of `site-url` aren't external.
+    pub external_links: Option<bool>,
+    /// Shows a bar at the top of the chapters with how much of them has been scrolled.
+    pub reading_progress: Option<bool>,
+    /// Opens the images of the chapters in a full-screen overlay when clicked.
+    pub lightbox: Option<bool>,
+    /// Generates a service worker and a web app manifest so the book can be read offline
+    /// after a first visit. Off by default, a stale cache is surprising for a website.
+    pub offline: Option<bool>,
+    /// Height in pixels of the sticky header, the targets of in-page links are scrolled below it.
+    pub header_height: Option<u16>,
+    /// Initial width in pixels of the sidebar, readers can resize it and their width is kept.
+    pub sidebar_width: Option<u16>,
     pub print: Option<HtmlPrint>,
     pub fold: Option<HtmlFold>,
     pub playground: Option<HtmlPlayground>,
     pub code: Option<HtmlCode>,
+    /// Themes coloring the highlighted code for each color scheme, see [`CodeTheme`].
+    pub code_theme: Option<CodeTheme>,
+    /// Self-hosted web fonts of the pages, see [`Fonts`]. The system fonts are used without it.
+    pub fonts: Option<Fonts>,
     pub search: Option<HtmlSearch>,
     pub redirect: Option<HtmlRedirect>,
+    /// Static host to write `_headers` and `_redirects` files for, with the cache rules of the
+    /// pages and the redirects of `[output.html.redirect]`.
+    pub hosting: Option<Hosting>,
+    /// Whether search engines may index the book, see [`Robots`]. Allowed by default.
+    pub robots: Option<Robots>,
+    /// Content security policy of the pages, written as a `<meta http-equiv>` tag. The inline
+    /// scripts and styles of each page are allowed by their hash, added to `script-src` and
+    /// `style-src`, so the features of the theme keep working.
+    pub csp: Option<String>,
+    /// Analytics script loaded by every page, see [`Analytics`].
+    pub analytics: Option<Analytics>,
+    /// Shows a banner asking the readers before loading the `analytics` script, which isn't
+    /// loaded until they accept. Their answer is remembered.
+    pub consent: Option<bool>,
+    /// Text of the theme's interface, for every language.
+    pub ui: Option<UiStrings>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct HtmlPrint {
     /// include support for printable output
-    enable: bool,
+    pub enable: bool,
     /// insert page-break after each chapter
-    page_break: Option<bool>,
+    pub page_break: Option<bool>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -73,23 +141,67 @@ pub struct HtmlFold {
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct HtmlPlayground {
-    ///
     editable: Option<bool>,
-    ///
     copyable: Option<bool>,
-    ///
     copy_js: Option<bool>,
-    ///
-    runnable: Option<bool>,
-    ///
+    /// Adds a button running the Rust code blocks on the Rust playground, showing their output.
+    pub runnable: Option<bool>,
     line_numbers: Option<bool>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct HtmlCode {
-    ///
     hidelines: Option<HashMap<String, String>>,
+    /// Size in bytes above which a code block is shown collapsed and without highlighting,
+    /// 256 KiB by default. A huge log pasted in a chapter doesn't blow up the page this way.
+    pub large_block_size: Option<usize>,
+}
+
+/// Syntect themes coloring the highlighted code, by name, like `InspiredGitHub`,
+/// `Solarized (light)`, `base16-ocean.dark` or `base16-mocha.dark`. The dark one applies while
+/// the theme switch sets the `dark` class on the page.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CodeTheme {
+    /// `InspiredGitHub` by default.
+    pub light: Option<String>,
+    /// `base16-ocean.dark` by default.
+    pub dark: Option<String>,
+}
+
+/// Web fonts shipped with the book, copied to `fonts/` in the output at their path in the book:
+/// ```toml
+/// [output.html.fonts]
+/// body = "Inter"
+/// code = "JetBrains Mono"
+/// [[output.html.fonts.faces]]
+/// family = "Inter"
+/// src = "theme/fonts/Inter.woff2"
+/// weight = "100 900"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Fonts {
+    /// Family of the text, before the system fonts. Its first face is preloaded.
+    pub body: Option<String>,
+    /// Family of the code, before the system monospace fonts.
+    pub code: Option<String>,
+    /// Files of the families, each one an `@font-face`.
+    pub faces: Vec<FontFace>,
+}
+
+/// A font file and the `@font-face` describing it.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FontFace {
+    pub family: String,
+    /// Path of the file, relative to the book root.
+    pub src: String,
+    /// `font-weight`, like `700` or `100 900` for a variable font.
+    pub weight: Option<String>,
+    /// `font-style`, like `italic`.
+    pub style: Option<String>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -110,6 +222,59 @@ pub struct HtmlSearch {
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct HtmlRedirect {
-    ///
-    redirect: HashMap<String, String>,
+    /// Old page, like `/old/page.html`, to the location it moved to. Relative locations are
+    /// relative to the old page.
+    #[serde(flatten)]
+    pub redirect: HashMap<String, String>,
+}
+
+/// How `[output.html] anchor-style` turns the text of the headings into anchors, so the links
+/// of a migrated book keep landing on their heading. Both drop the emoji and keep the digits,
+/// `_` and `-`, and number a repeated anchor `-1`, `-2`, ...
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnchorStyle {
+    /// Like mdBook: only the ASCII letters are lowercased, every whitespace character becomes
+    /// `-` and the punctuation is dropped. `Über Uns?` is `Über-uns`.
+    #[default]
+    Mdbook,
+    /// Like GitHub: every letter is lowercased, each space becomes `-` and the other
+    /// whitespace characters are dropped with the punctuation. `Über Uns?` is `über-uns`.
+    Github,
+}
+
+/// The `robots.txt` written by the build.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Robots {
+    /// Lets every crawler in, pointing them to the sitemap.
+    #[default]
+    Allow,
+    /// Keeps crawlers out and marks every page `noindex`, for staging builds.
+    Disallow,
+}
+
+/// The `[output.html.analytics]` script, like Plausible or GoatCounter:
+/// ```toml
+/// [output.html.analytics]
+/// src = "https://plausible.io/js/script.js"
+/// data-domain = "example.com"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Analytics {
+    /// URL of the script.
+    pub src: String,
+    /// Other attributes of the `<script>` tag, like `data-domain` or `data-goatcounter`.
+    #[serde(flatten)]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Static hosts `[output.html] hosting` writes configuration files for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hosting {
+    /// Writes `_headers` and `_redirects`.
+    Netlify,
+    /// Cloudflare Pages reads the same `_headers` and `_redirects` files as Netlify.
+    Cloudflare,
 }
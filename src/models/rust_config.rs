@@ -21,3 +21,14 @@ pub enum RustEdition {
     #[serde(rename = "2015")]
     E2015,
 }
+
+impl RustEdition {
+    /// The edition as the playground names it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RustEdition::E2021 => "2021",
+            RustEdition::E2018 => "2018",
+            RustEdition::E2015 => "2015",
+        }
+    }
+}
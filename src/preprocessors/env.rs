@@ -0,0 +1,107 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::models::Chapter;
+
+const OPEN: &str = "{{#env";
+
+/// Replaces `{{#env NAME}}` with the value of the `NAME` environment variable at build time, an
+/// unset variable is an error unless the directive has a default, like `{{#env NAME:latest}}`.
+/// Only the variables of `allowed`, `[build] allowed-env`, can be read.
+pub fn run(chapters: &mut [Chapter], allowed: &[String]) -> Result<()> {
+    for chapter in chapters.iter_mut() {
+        let Some(content) = &chapter.content else {
+            continue;
+        };
+        if !content.contains(OPEN) {
+            continue;
+        }
+        let slug = chapter.slug.clone().unwrap_or_default();
+        let var = |name: &str| std::env::var(name).ok();
+        chapter.content = Some(expand(content, &slug, allowed, var)?);
+    }
+
+    Ok(())
+}
+
+/// Expands the directives of `content`, reading the variables of `allowed` with `var`.
+fn expand(
+    content: &str,
+    slug: &str,
+    allowed: &[String],
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find(OPEN) {
+        output.push_str(&rest[..start]);
+
+        let directive = &rest[start + OPEN.len()..];
+        let end = directive
+            .find("}}")
+            .ok_or_else(|| anyhow!("Unclosed `{OPEN}` directive in `{slug}`"))?;
+        let (name, default) = match directive[..end].trim().split_once(':') {
+            Some((name, default)) => (name.trim(), Some(default)),
+            None => (directive[..end].trim(), None),
+        };
+
+        if !allowed.iter().any(|allowed| allowed == name) {
+            bail!(
+                "`{slug}` uses the environment variable `{name}`, add it to `allowed-env` of \
+                 `[build]` in book.toml to allow it"
+            );
+        }
+        let value = var(name)
+            .or_else(|| default.map(str::to_string))
+            .ok_or_else(|| {
+                anyhow!("`{slug}` uses the environment variable `{name}`, but it isn't set and has no default, like `{{{{#env {name}:default}}}}`")
+            })?;
+        output.push_str(&value);
+
+        rest = &directive[end + 2..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Option<String> {
+        (name == "VERSION").then(|| "1.2.0".to_string())
+    }
+
+    fn allowed() -> Vec<String> {
+        vec!["VERSION".to_string(), "CHANNEL".to_string()]
+    }
+
+    #[test]
+    fn directives_are_replaced_by_the_variable_or_the_default() {
+        let content = "v{{#env VERSION}} on {{#env CHANNEL:stable}}, {{#env VERSION:0}}";
+
+        assert_eq!(
+            expand(content, "intro", &allowed(), var).unwrap(),
+            "v1.2.0 on stable, 1.2.0"
+        );
+    }
+
+    #[test]
+    fn unset_variables_without_a_default_are_an_error() {
+        let error = expand("{{#env CHANNEL}}", "intro", &allowed(), var).unwrap_err();
+
+        assert!(error.to_string().contains("`CHANNEL`"));
+    }
+
+    #[test]
+    fn variables_outside_the_allowlist_are_an_error() {
+        assert!(expand("{{#env HOME:none}}", "intro", &allowed(), var).is_err());
+        assert!(expand("{{#env VERSION}}", "intro", &[], var).is_err());
+    }
+
+    #[test]
+    fn unclosed_directives_are_an_error() {
+        assert!(expand("{{#env VERSION", "intro", &allowed(), var).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::{anyhow, Result};
+
+const OPEN: &str = "{{#lang";
+const CLOSE: &str = "{{/lang}}";
+
+/// Keeps the `{{#lang es pt}}...{{/lang}}` blocks written for `language` and strips the
+/// blocks of every other language.
+pub fn run(content: &str, language: &str) -> Result<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find(OPEN) {
+        output.push_str(&rest[..start]);
+
+        let directive = &rest[start + OPEN.len()..];
+        let header_end = directive
+            .find("}}")
+            .ok_or_else(|| anyhow!("Unclosed `{OPEN}` directive"))?;
+        let mut languages = directive[..header_end].split_whitespace();
+
+        let body = &directive[header_end + 2..];
+        let body_end = body
+            .find(CLOSE)
+            .ok_or_else(|| anyhow!("`{OPEN}` block without a matching `{CLOSE}`"))?;
+        if languages.any(|lang| lang == language) {
+            output.push_str(&body[..body_end]);
+        }
+
+        rest = &body[body_end + CLOSE.len()..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
@@ -0,0 +1,7 @@
+//! Transformations applied to the markdown of the chapters before it's rendered.
+
+pub mod env;
+pub mod lang;
+pub mod tabs;
+pub mod toc;
+pub mod transclude;
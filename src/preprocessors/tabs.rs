@@ -0,0 +1,192 @@
+use anyhow::{bail, Result};
+
+use crate::models::Chapter;
+use crate::renderer::markdown::{closes_fence, code_fence, escape_attr, escape_html};
+
+const OPEN: &str = "{{#tabs}}";
+const CLOSE: &str = "{{#endtabs}}";
+
+/// Groups the code blocks between `{{#tabs}}` and `{{#endtabs}}` lines into tabs showing one
+/// block at a time. A tab is labeled by the `tab=` key of the info string, like
+/// ` ```js tab="Node.js" `, or else by the language of the block.
+pub fn run(chapters: &mut [Chapter]) -> Result<()> {
+    for chapter in chapters.iter_mut() {
+        let Some(content) = &chapter.content else {
+            continue;
+        };
+        if !content.contains(OPEN) {
+            continue;
+        }
+        let slug = chapter.slug.clone().unwrap_or_default();
+        chapter.content = Some(expand(content, &slug)?);
+    }
+
+    Ok(())
+}
+
+/// A code block of a tab group.
+struct Tab {
+    label: String,
+    /// The block itself, without the `tab=` key.
+    markdown: String,
+}
+
+/// Expands the tab groups of `content`, the ids of their tabs start with `slug`.
+fn expand(content: &str, slug: &str) -> Result<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut lines = content.lines();
+    let mut groups = 0;
+
+    while let Some(line) = lines.next() {
+        if line.trim() != OPEN {
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        }
+
+        let mut tabs = Vec::new();
+        let mut closed = false;
+        while let Some(line) = lines.next() {
+            let trimmed = line.trim();
+            if trimmed == CLOSE {
+                closed = true;
+                break;
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+            let Some(fence) = code_fence(trimmed) else {
+                bail!("`{slug}` has `{trimmed}` inside `{OPEN}`, only code blocks can be tabs");
+            };
+            let (label, info) = tab_label(&trimmed[fence.len()..]);
+            let mut markdown = format!("{fence}{info}\n");
+            for line in lines.by_ref() {
+                markdown.push_str(line);
+                markdown.push('\n');
+                if closes_fence(line, fence) {
+                    break;
+                }
+            }
+            tabs.push(Tab { label, markdown });
+        }
+        if !closed {
+            bail!("`{slug}` has a `{OPEN}` without its `{CLOSE}`");
+        }
+
+        output.push_str(&render(&tabs, &format!("{slug}-tabs-{groups}")));
+        groups += 1;
+    }
+
+    Ok(output)
+}
+
+/// The label of a tab from the `info` string of its block, and the info string without the
+/// `tab=` key.
+fn tab_label(info: &str) -> (String, String) {
+    let language = info.split_whitespace().next().unwrap_or_default().to_string();
+    let Some(start) = info.find("tab=") else {
+        return (language, info.to_string());
+    };
+
+    let value = &info[start + "tab=".len()..];
+    let (label, end) = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], (end + 2).min(value.len()))
+        }
+        None => {
+            let end = value.find(char::is_whitespace).unwrap_or(value.len());
+            (&value[..end], end)
+        }
+    };
+    let rest = format!("{}{}", &info[..start], &value[end..]);
+    (label.to_string(), rest.trim_end().to_string())
+}
+
+/// The tab list and a panel for each block, the first one selected. Blank lines around the
+/// blocks keep them markdown inside the HTML.
+fn render(tabs: &[Tab], id: &str) -> String {
+    let mut list = String::new();
+    let mut panels = String::new();
+    for (index, tab) in tabs.iter().enumerate() {
+        let selected = index == 0;
+        let tab_id = escape_attr(&format!("{id}-{index}"));
+        let label = escape_attr(&tab.label);
+        list.push_str(&format!(
+            r#"<button type="button" role="tab" id="{tab_id}" aria-controls="{tab_id}-panel" aria-selected="{selected}" tabindex="{}" data-tab="{label}">{}</button>"#,
+            if selected { 0 } else { -1 },
+            escape_html(&tab.label),
+        ));
+        panels.push_str(&format!(
+            "<div role=\"tabpanel\" id=\"{tab_id}-panel\" aria-labelledby=\"{tab_id}\"{}>\n\n{}\n</div>\n",
+            if selected { "" } else { " hidden" },
+            tab.markdown,
+        ));
+    }
+
+    format!("<div class=\"code-tabs\">\n<div role=\"tablist\" class=\"code-tabs-list\">{list}</div>\n{panels}</div>\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::markdown_config::MarkdownConfig;
+    use crate::renderer::markdown::{render_markdown, MarkdownSettings};
+
+    #[test]
+    fn code_blocks_between_the_markers_become_tabs() {
+        let content = "Before\n\n{{#tabs}}\n```js tab=\"Node.js\"\nconsole.log(1)\n```\n\n\
+                       ````py\nprint(1)\n```\nprint(2)\n````\n{{#endtabs}}\nAfter\n";
+        let html = expand(content, "setup").unwrap();
+
+        assert!(html.starts_with("Before\n\n<div class=\"code-tabs\">"));
+        assert!(html.ends_with("</div>\nAfter\n"));
+        assert!(html.contains(
+            r#"id="setup-tabs-0-0" aria-controls="setup-tabs-0-0-panel" aria-selected="true" tabindex="0" data-tab="Node.js">Node.js</button>"#
+        ));
+        assert!(html.contains(r#"aria-selected="false" tabindex="-1" data-tab="py">py</button>"#));
+        assert!(html.contains("```js\nconsole.log(1)\n```\n"));
+        // The shorter fence inside the block doesn't close it
+        assert!(html.contains("````py\nprint(1)\n```\nprint(2)\n````\n"));
+        assert!(html.contains(r#"aria-labelledby="setup-tabs-0-1" hidden>"#));
+    }
+
+    #[test]
+    fn tab_labels_come_from_the_key_or_the_language() {
+        assert_eq!(tab_label("rust"), ("rust".to_string(), "rust".to_string()));
+        assert_eq!(
+            tab_label("js tab=\"Node.js\" ignore"),
+            ("Node.js".to_string(), "js  ignore".to_string())
+        );
+        assert_eq!(tab_label("sh tab=Bash"), ("Bash".to_string(), "sh".to_string()));
+    }
+
+    #[test]
+    fn tabs_survive_the_sanitizing_of_the_chapter() {
+        let settings = MarkdownSettings {
+            extensions: MarkdownConfig {
+                sanitize_html: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let content = "<script>alert(1)</script>\n\n\
+                       {{#tabs}}\n```js\nrun()\n```\n```py\nrun()\n```\n{{#endtabs}}\n";
+        let html = render_markdown(&expand(content, "setup").unwrap(), None, &settings);
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains(r#"<div role="tablist" class="code-tabs-list">"#));
+        assert!(html.contains(
+            r#"<button type="button" role="tab" id="setup-tabs-0-0" aria-controls="setup-tabs-0-0-panel" aria-selected="true" tabindex="0" data-tab="js">js</button>"#
+        ));
+        assert!(html.contains(
+            r#"<div role="tabpanel" id="setup-tabs-0-1-panel" aria-labelledby="setup-tabs-0-1" hidden="">"#
+        ));
+    }
+
+    #[test]
+    fn only_closed_groups_of_code_blocks_are_expanded() {
+        assert!(expand("{{#tabs}}\nSome text\n{{#endtabs}}\n", "a").is_err());
+        assert!(expand("{{#tabs}}\n```rust\n```\n", "a").is_err());
+    }
+}
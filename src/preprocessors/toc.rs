@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+
+use crate::default_theme::links::page_href;
+use crate::models::Chapter;
+
+const DIRECTIVE: &str = "{{#toc";
+
+/// Replaces the `{{#toc}}` lines with a list of links to the chapters of the navigation, nested
+/// like in `SUMMARY.md`. Runs once the chapters are numbered, the list shows their numbers.
+///
+/// - `{{#toc section}}` only lists the chapters nested under the current one.
+/// - `{{#toc level=2}}` stops at the second level of the list.
+pub fn run(chapters: &mut [Chapter], trailing_slash: Option<bool>) -> Result<()> {
+    for index in 0..chapters.len() {
+        let Some(content) = &chapters[index].content else {
+            continue;
+        };
+        if !content.contains(DIRECTIVE) {
+            continue;
+        }
+
+        let mut expanded = String::with_capacity(content.len());
+        for line in content.lines() {
+            match parse(line.trim()) {
+                Some(options) => expanded.push_str(&list(chapters, index, options?, trailing_slash)),
+                None => {
+                    expanded.push_str(line);
+                    expanded.push('\n');
+                }
+            }
+        }
+        chapters[index].content = Some(expanded);
+    }
+
+    Ok(())
+}
+
+/// Arguments of a `{{#toc}}` directive.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    /// Only the chapters nested under the current one.
+    section: bool,
+    /// Deepest level listed, 1 for the first one.
+    level: Option<usize>,
+}
+
+/// The options of the directive on `line`, `None` when it isn't one.
+fn parse(line: &str) -> Option<Result<Options>> {
+    let arguments = line.strip_prefix(DIRECTIVE)?.strip_suffix("}}")?;
+    if !arguments.is_empty() && !arguments.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut options = Options::default();
+    for argument in arguments.split_whitespace() {
+        match argument.split_once('=') {
+            None if argument == "section" => options.section = true,
+            Some(("level", level)) => match level.parse::<usize>() {
+                Ok(level) if level > 0 => options.level = Some(level),
+                _ => return Some(Err(anyhow!("`{line}` needs a level of 1 or more"))),
+            },
+            _ => return Some(Err(anyhow!("`{line}` has an unknown argument `{argument}`"))),
+        }
+    }
+    Some(Ok(options))
+}
+
+/// Markdown list of the chapters `options` selects for the chapter at `current`.
+fn list(chapters: &[Chapter], current: usize, options: Options, trailing_slash: Option<bool>) -> String {
+    let (listed, top) = if options.section {
+        let depth = chapters[current].depth;
+        let end = chapters[current + 1..]
+            .iter()
+            .position(|chapter| chapter.depth <= depth)
+            .map_or(chapters.len(), |position| current + 1 + position);
+        (&chapters[current + 1..end], depth + 1)
+    } else {
+        (chapters, 0)
+    };
+
+    let mut markdown = String::new();
+    for (index, chapter) in listed.iter().enumerate() {
+        let Some(slug) = &chapter.slug else {
+            continue;
+        };
+        let level = chapter.depth.saturating_sub(top);
+        let too_deep = options.level.is_some_and(|max| level >= max);
+        // The overview doesn't link to itself
+        let is_current = !options.section && index == current;
+        if !chapter.in_toc() || too_deep || is_current {
+            continue;
+        }
+        markdown.push_str(&format!(
+            "{}- [{}]({})\n",
+            "  ".repeat(level),
+            chapter.numbered_title(),
+            page_href(slug, trailing_slash)
+        ));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(slug: &str, depth: usize) -> Chapter {
+        Chapter {
+            title: slug.to_uppercase(),
+            slug: Some(slug.to_string()),
+            depth,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn directives_take_a_section_and_a_level() {
+        assert_eq!(parse("not a directive").map(Result::ok), None);
+        assert_eq!(parse("{{#toctree}}").map(Result::ok), None);
+        assert_eq!(parse("{{#toc}}").and_then(Result::ok), Some(Options::default()));
+        assert_eq!(
+            parse("{{#toc section level=2}}").and_then(Result::ok),
+            Some(Options { section: true, level: Some(2) })
+        );
+        assert!(parse("{{#toc level=0}}").is_some_and(|options| options.is_err()));
+    }
+
+    #[test]
+    fn section_lists_the_nested_chapters() {
+        let mut chapters = vec![chapter("a", 0), chapter("b", 1), chapter("c", 2), chapter("d", 0)];
+        chapters[0].content = Some("{{#toc section}}\n".to_string());
+        chapters[3].content = Some("{{#toc level=1}}\n".to_string());
+        run(&mut chapters, None).unwrap();
+
+        assert_eq!(chapters[0].content.as_deref(), Some("- [B](b.html)\n  - [C](c.html)\n"));
+        assert_eq!(chapters[3].content.as_deref(), Some("- [A](a.html)\n"));
+    }
+}
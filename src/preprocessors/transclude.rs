@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::models::Chapter;
+use crate::renderer::markdown::{section, MarkdownSettings};
+
+const OPEN: &str = "{{#transclude";
+
+/// Replaces `{{#transclude other-chapter#section}}` with the markdown of the `section` heading
+/// of the chapter with the `other-chapter` slug, or with the whole chapter without `#section`.
+/// The transcluded markdown is expanded too, chapters transcluding each other are an error.
+pub fn run(chapters: &mut [Chapter], settings: &MarkdownSettings) -> Result<()> {
+    let sources = chapters
+        .iter()
+        .filter_map(|chapter| Some((chapter.slug.clone()?, chapter.content.clone()?)))
+        .collect::<HashMap<_, _>>();
+
+    for chapter in chapters.iter_mut() {
+        let (Some(slug), Some(content)) = (&chapter.slug, &chapter.content) else {
+            continue;
+        };
+        if !content.contains(OPEN) {
+            continue;
+        }
+        let content = expand(content, &sources, settings, &mut vec![slug.clone()])?;
+        chapter.content = Some(content);
+    }
+
+    Ok(())
+}
+
+/// Expands the directives of `content`, `stack` holds the chapters being transcluded, the last
+/// one being the owner of `content`.
+fn expand(
+    content: &str,
+    sources: &HashMap<String, String>,
+    settings: &MarkdownSettings,
+    stack: &mut Vec<String>,
+) -> Result<String> {
+    let current = stack.last().cloned().unwrap_or_default();
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find(OPEN) {
+        output.push_str(&rest[..start]);
+
+        let directive = &rest[start + OPEN.len()..];
+        let end = directive
+            .find("}}")
+            .ok_or_else(|| anyhow!("Unclosed `{OPEN}` directive in `{current}`"))?;
+        let target = directive[..end].trim();
+        let (slug, anchor) = match target.split_once('#') {
+            Some((slug, anchor)) => (slug, Some(anchor)),
+            None => (target, None),
+        };
+
+        if stack.iter().any(|chapter| chapter == slug) {
+            bail!(
+                "`{current}` can't transclude `{target}`, it's a cycle: {} -> {slug}",
+                stack.join(" -> ")
+            );
+        }
+        let source = sources
+            .get(slug)
+            .ok_or_else(|| anyhow!("`{current}` transcludes `{target}`, but there's no chapter `{slug}`"))?;
+        let fragment = match anchor {
+            Some(anchor) => section(source, anchor, settings).ok_or_else(|| {
+                anyhow!("`{current}` transcludes `{target}`, but `{slug}` has no `#{anchor}` heading")
+            })?,
+            None => source,
+        };
+
+        stack.push(slug.to_string());
+        output.push_str(&expand(fragment, sources, settings, stack)?);
+        stack.pop();
+
+        rest = &directive[end + 2..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
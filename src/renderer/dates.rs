@@ -0,0 +1,117 @@
+//! Dates shown on the pages, like the `date` and `updated` of the chapters, written in the
+//! format and timezone of `[output.html] date-format` and `timezone`. Machine-readable dates,
+//! the `datetime` attributes and the structured data, stay in ISO 8601.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Locale, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+
+use crate::models::Config;
+
+/// Format of the dates without `[output.html] date-format`, like `1 May 2024`.
+pub const DEFAULT_DATE_FORMAT: &str = "%-d %B %Y";
+
+/// How the dates are written on the pages.
+#[derive(Debug, Clone)]
+pub struct DateSettings {
+    /// `strftime` format, with month and day names in the language of the page.
+    pub format: String,
+    /// Timezone dates with a time are converted to, UTC by default.
+    pub timezone: Tz,
+    /// Language of the book, for the pages without their own.
+    pub language: String,
+}
+
+impl Default for DateSettings {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_DATE_FORMAT.to_string(),
+            timezone: Tz::UTC,
+            language: String::new(),
+        }
+    }
+}
+
+impl DateSettings {
+    /// Reads `[output.html] date-format` and `timezone`, an invalid format or an unknown
+    /// timezone is an error.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let html = config.html_config();
+        let format = html.date_format.unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_string());
+        if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+            bail!("`[output.html] date-format` isn't a valid strftime format: `{format}`");
+        }
+        let timezone = match html.timezone {
+            Some(timezone) => timezone.parse::<Tz>().map_err(|_| {
+                anyhow!("`[output.html] timezone` isn't a known timezone, like `Europe/Paris`: `{timezone}`")
+            })?,
+            None => Tz::UTC,
+        };
+
+        Ok(Self {
+            format,
+            timezone,
+            language: config.book.language.clone().unwrap_or_default(),
+        })
+    }
+
+    /// `date`, a `2024-05-01` day or an RFC 3339 date and time, written for a page in
+    /// `language`. `None` when it's neither.
+    pub fn format(&self, date: &str, language: &str) -> Option<String> {
+        let date = date.trim();
+        let local = match DateTime::parse_from_rfc3339(date) {
+            Ok(date) => date.with_timezone(&self.timezone),
+            // A day has no time to convert, it's the same day everywhere
+            Err(_) => {
+                let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+                self.timezone.from_local_datetime(&day.and_hms_opt(0, 0, 0)?).earliest()?
+            }
+        };
+        let language = if language.is_empty() { &self.language } else { language };
+
+        Some(local.format_localized(&self.format, locale(language)).to_string())
+    }
+}
+
+/// Locale of `language`, like `fr` or `pt-BR`, English when there's none for it.
+fn locale(language: &str) -> Locale {
+    let language = language.replace('-', "_");
+    let with_region = format!("{language}_{}", language.to_ascii_uppercase());
+    let locale = [language.as_str(), with_region.as_str()]
+        .into_iter()
+        .find_map(|name| Locale::try_from(name).ok())
+        .unwrap_or(Locale::POSIX);
+    locale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_use_the_default_format() {
+        let dates = DateSettings::default();
+
+        assert_eq!(dates.format("2024-05-01", "").as_deref(), Some("1 May 2024"));
+        assert_eq!(dates.format("yesterday", ""), None);
+    }
+
+    #[test]
+    fn times_are_converted_to_the_timezone() {
+        let dates = DateSettings {
+            format: "%Y-%m-%d %H:%M".to_string(),
+            timezone: Tz::Europe__Paris,
+            language: String::new(),
+        };
+
+        assert_eq!(dates.format("2024-05-01T22:30:00Z", "").as_deref(), Some("2024-05-02 00:30"));
+    }
+
+    #[test]
+    fn month_names_follow_the_language() {
+        let dates = DateSettings::default();
+
+        assert_eq!(dates.format("2024-05-01", "fr").as_deref(), Some("1 mai 2024"));
+    }
+}
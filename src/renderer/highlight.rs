@@ -0,0 +1,105 @@
+//! Syntax highlighting of the code blocks with syntect. The tokens are classed spans, colored by
+//! the stylesheets of the light and dark code themes, so switching the theme of the page doesn't
+//! need the chapters rendered again.
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Code theme of the light color scheme without `[output.html.code-theme] light`.
+pub const DEFAULT_LIGHT_THEME: &str = "InspiredGitHub";
+/// Code theme of the dark color scheme without `[output.html.code-theme] dark`.
+pub const DEFAULT_DARK_THEME: &str = "base16-ocean.dark";
+/// Class the theme switch toggles on `<html>` for the dark color scheme.
+const DARK_CLASS: &str = ".dark";
+
+/// Prefixed, so the classes of the tokens (`syn-keyword`...) don't clash with the theme's.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "syn-" };
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEMES: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// The HTML of `code` with its tokens in classed spans, `None` when `language` is unknown.
+pub fn highlight(code: &str, language: &str) -> Option<String> {
+    let syntax = SYNTAXES.find_syntax_by_token(language)?;
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAXES, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line).ok()?;
+    }
+    Some(generator.finalize())
+}
+
+/// Stylesheet coloring the highlighted code with the syntect theme `light`, and with `dark`
+/// while the page has the dark class.
+pub fn theme_css(light: &str, dark: &str) -> Result<String> {
+    let light = css_for_theme_with_class_style(theme(light)?, CLASS_STYLE)?;
+    let dark = css_for_theme_with_class_style(theme(dark)?, CLASS_STYLE)?;
+    Ok(format!("{light}\n{}", scoped(&dark, DARK_CLASS)))
+}
+
+fn theme(name: &str) -> Result<&'static syntect::highlighting::Theme> {
+    THEMES.themes.get(name).ok_or_else(|| {
+        let names = THEMES.themes.keys().map(String::as_str).collect::<Vec<_>>();
+        anyhow!("Unknown code theme `{name}`, the themes are: {}", names.join(", "))
+    })
+}
+
+/// Prefixes every selector of `css` with `scope`. The rules of syntect are flat, only its header
+/// comment comes before a selector.
+fn scoped(css: &str, scope: &str) -> String {
+    let mut output = String::with_capacity(css.len() * 3 / 2);
+    for rule in css.split_inclusive('}') {
+        let Some((selectors, body)) = rule.split_once('{') else {
+            output.push_str(rule);
+            continue;
+        };
+        let (comment, selectors) = match selectors.rfind("*/") {
+            Some(end) => selectors.split_at(end + 2),
+            None => ("", selectors),
+        };
+        let selectors = selectors
+            .split(',')
+            .map(|selector| format!("{scope} {}", selector.trim()))
+            .collect::<Vec<_>>();
+        output.push_str(&format!("{comment}\n{} {{{body}", selectors.join(", ")));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_languages_are_highlighted_with_prefixed_classes() {
+        let html = highlight("fn main() {}\n", "rust").unwrap();
+
+        assert!(html.contains(r#"class="syn-"#));
+        assert!(html.contains("main"));
+        assert!(highlight("fn main() {}\n", "not-a-language").is_none());
+    }
+
+    #[test]
+    fn the_dark_theme_only_applies_under_the_dark_class() {
+        let css = theme_css(DEFAULT_LIGHT_THEME, DEFAULT_DARK_THEME).unwrap();
+        let (light, dark) = css.split_once(".dark ").unwrap();
+
+        assert!(!light.contains(".dark"));
+        assert!(dark.contains(".dark .syn-"));
+        assert!(theme_css("Not a theme", DEFAULT_DARK_THEME).is_err());
+    }
+
+    #[test]
+    fn every_selector_is_scoped() {
+        let css = "/*\n * theme\n */\n.a {\n color: red;\n}\n.b .c, .d {\n color: blue;\n}\n";
+
+        assert_eq!(
+            scoped(css, ".dark"),
+            "/*\n * theme\n */\n.dark .a {\n color: red;\n}\n\
+             .dark .b .c, .dark .d {\n color: blue;\n}\n"
+        );
+    }
+}
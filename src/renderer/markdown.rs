@@ -0,0 +1,982 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{html, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use crate::models::markdown_config::MarkdownConfig;
+use crate::models::preprocessors_config::AnchorStyle;
+use crate::models::Config;
+use crate::renderer::highlight::highlight;
+
+/// Size in bytes above which a code block is collapsed and not highlighted by default.
+pub const DEFAULT_LARGE_CODE_BLOCK: usize = 256 * 1024;
+
+/// Book-wide settings of the markdown renderer.
+#[derive(Debug, Clone)]
+pub struct MarkdownSettings {
+    /// Adds a `data-source-line` attribute with the markdown line to block elements,
+    /// see `[output.html] source-lines`.
+    pub source_lines: bool,
+    /// Replaces GitHub emoji shortcodes (`:rocket:`) outside of code, see `[output.html] emoji`.
+    pub emoji: bool,
+    /// Markdown extensions, see `[markdown]`.
+    pub extensions: MarkdownConfig,
+    /// Size in bytes above which a code block is collapsed and not highlighted, see
+    /// `[output.html.code] large-block-size`.
+    pub large_code_block: usize,
+    /// How the headings are turned into anchors, see `[output.html] anchor-style`.
+    pub anchor_style: AnchorStyle,
+}
+
+impl Default for MarkdownSettings {
+    fn default() -> Self {
+        Self {
+            source_lines: false,
+            emoji: false,
+            extensions: MarkdownConfig::default(),
+            large_code_block: DEFAULT_LARGE_CODE_BLOCK,
+            anchor_style: AnchorStyle::default(),
+        }
+    }
+}
+
+impl MarkdownSettings {
+    pub fn from_config(config: &Config) -> Self {
+        let html = config.html_config();
+
+        Self {
+            source_lines: html.source_lines.unwrap_or(false),
+            emoji: html.emoji.unwrap_or(false),
+            extensions: config.markdown.clone().unwrap_or_default(),
+            large_code_block: html
+                .code
+                .and_then(|code| code.large_block_size)
+                .unwrap_or(DEFAULT_LARGE_CODE_BLOCK),
+            anchor_style: html.anchor_style.unwrap_or_default(),
+        }
+    }
+
+    /// pulldown-cmark options enabling the configured extensions.
+    pub fn options(&self) -> Options {
+        let extensions = &self.extensions;
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_TABLES, extensions.tables);
+        options.set(Options::ENABLE_FOOTNOTES, extensions.footnotes);
+        options.set(Options::ENABLE_STRIKETHROUGH, extensions.strikethrough);
+        options.set(Options::ENABLE_TASKLISTS, extensions.tasklists);
+        options.set(Options::ENABLE_SMART_PUNCTUATION, extensions.smart_punctuation);
+        options.set(Options::ENABLE_HEADING_ATTRIBUTES, extensions.heading_attributes);
+        options
+    }
+}
+
+/// Renders the markdown of a chapter to HTML.
+///
+/// Headings without an explicit `{#id}` get one derived from their text, unique within the
+/// chapter. When `id_prefix` is given, heading ids and links to them (`#anchor`) are prefixed
+/// with it, so several chapters can share a page without their anchors colliding.
+pub fn render_markdown(
+    content: &str,
+    id_prefix: Option<&str>,
+    settings: &MarkdownSettings,
+) -> String {
+    let expanded = settings
+        .extensions
+        .definition_lists
+        .then(|| definition_lists(content, settings.options()))
+        .flatten();
+    let content = expanded.as_deref().unwrap_or(content);
+    let (mut events, offsets): (Vec<_>, Vec<_>) =
+        Parser::new_ext(content, settings.options()).into_offset_iter().unzip();
+    let mut used_ids = HashMap::new();
+
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { id, .. }) => {
+                let base = match id {
+                    Some(id) => id.to_string(),
+                    None => slugify(&heading_text(&events[i + 1..]), settings.anchor_style),
+                };
+                let unique = unique_id(base, &mut used_ids);
+                let unique = match id_prefix {
+                    Some(prefix) => format!("{prefix}-{unique}"),
+                    None => unique,
+                };
+
+                if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+                    *id = Some(unique.into());
+                }
+            }
+            Event::Start(Tag::Link { dest_url, .. }) if dest_url.starts_with('#') => {
+                let Some(prefix) = id_prefix else {
+                    continue;
+                };
+                let anchor = format!("#{prefix}-{}", &dest_url[1..]);
+
+                if let Event::Start(Tag::Link { dest_url, .. }) = &mut events[i] {
+                    *dest_url = anchor.into();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if settings.emoji {
+        let mut in_code_block = false;
+        let mut i = 0;
+        while i < events.len() {
+            match &events[i] {
+                Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                Event::End(TagEnd::CodeBlock) => in_code_block = false,
+                Event::Text(_) if !in_code_block => {
+                    // The parser splits the text at what could be markup, like the `_` of
+                    // `:white_check_mark:`. The texts are merged like `TextMergeStream` does,
+                    // but in place, so the offsets of the source lines still line up
+                    let start = i;
+                    let mut text = String::new();
+                    while let Some(Event::Text(part)) = events.get(i) {
+                        text.push_str(part);
+                        i += 1;
+                    }
+                    if text.contains(':') {
+                        events[start] = Event::Text(replace_shortcodes(&text).into());
+                        for event in &mut events[start + 1..i] {
+                            *event = Event::Text("".into());
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    // Task list markers become disabled checkboxes, with a class for styling them
+    for event in events.iter_mut() {
+        if let Event::TaskListMarker(checked) = event {
+            let checked = if *checked { " checked" } else { "" };
+            let html = format!(r#"<input type="checkbox" class="task-list-item-checkbox" disabled{checked}>"#);
+            *event = Event::Html(html.into());
+        }
+    }
+
+    collapse_large_code_blocks(&mut events, settings.large_code_block);
+    highlight_diffs(&mut events);
+    highlight_code(&mut events);
+    // Found before the source lines replace the opening tags of the code blocks
+    let captions = code_captions(&mut events);
+
+    if settings.source_lines {
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect::<Vec<_>>();
+
+        for (event, offset) in events.iter_mut().zip(offsets) {
+            let Event::Start(tag) = &*event else {
+                continue;
+            };
+            let line = line_starts.partition_point(|&start| start <= offset.start);
+            if let Some(html) = open_tag_with_line(tag, line) {
+                *event = Event::Html(html.into());
+            }
+        }
+    }
+
+    let mut output = String::with_capacity(content.len() * 3 / 2);
+    html::push_html(&mut output, with_captions(events, captions));
+    if settings.extensions.sanitize_html {
+        output = sanitize(&output);
+    }
+    output
+}
+
+/// Puts the section `number` of a chapter at the start of the first `<h1>` of its `html`, the
+/// title of the page. The anchor of the heading stays the one of its text.
+pub fn number_heading(html: &str, number: &str) -> String {
+    let Some(start) = html.find("<h1") else {
+        return html.to_string();
+    };
+    let Some(end) = html[start..].find('>').map(|end| start + end + 1) else {
+        return html.to_string();
+    };
+    format!(
+        r#"{}<span class="section-number">{}</span> {}"#,
+        &html[..end],
+        escape_html(number),
+        &html[end..]
+    )
+}
+
+/// Replaces the code blocks bigger than `limit` bytes with their escaped text in a collapsed
+/// `<details>`, so the passes highlighting code leave them alone.
+fn collapse_large_code_blocks(events: &mut [Event], limit: usize) {
+    let mut i = 0;
+    while i < events.len() {
+        let is_code_block = matches!(&events[i], Event::Start(Tag::CodeBlock(_)));
+        i += 1;
+        if !is_code_block {
+            continue;
+        }
+
+        let start = i;
+        while let Some(Event::Text(_)) = events.get(i) {
+            i += 1;
+        }
+        let size = events[start..i].iter().map(|event| match event {
+            Event::Text(text) => text.len(),
+            _ => 0,
+        });
+        if size.sum::<usize>() <= limit {
+            continue;
+        }
+
+        let mut code = String::new();
+        for event in &mut events[start..i] {
+            if let Event::Text(text) = event {
+                code.push_str(text);
+            }
+            *event = Event::Html("".into());
+        }
+        let lines = code.lines().count();
+        events[start - 1] = Event::Html(
+            format!(r#"<details class="large-code-block"><summary>{lines} lines</summary><pre><code>{}"#, escape_html(&code)).into(),
+        );
+        if let Some(end @ Event::End(TagEnd::CodeBlock)) = events.get_mut(i) {
+            *end = Event::Html("</code></pre></details>".into());
+        }
+    }
+}
+
+/// Size in bytes of the biggest code block of `content` over `limit`, `None` when they're all
+/// smaller.
+pub fn largest_code_block(content: &str, limit: usize) -> Option<usize> {
+    let mut sizes = Vec::new();
+    let mut current = None;
+    for event in Parser::new_ext(content, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => current = Some(0),
+            Event::Text(text) => {
+                if let Some(size) = current.as_mut() {
+                    *size += text.len();
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => sizes.extend(current.take()),
+            _ => {}
+        }
+    }
+    sizes.into_iter().filter(|size| *size > limit).max()
+}
+
+/// Wraps the lines of ```` ```diff ```` blocks in spans classed by their prefix: `diff-added`
+/// for `+`, `diff-removed` for `-` and `diff-hunk` for `@@` hunk headers.
+fn highlight_diffs(events: &mut [Event]) {
+    let mut i = 0;
+    while i < events.len() {
+        let is_diff = matches!(
+            &events[i],
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))
+                if info.split([',', ' ']).next() == Some("diff")
+        );
+        i += 1;
+        if !is_diff {
+            continue;
+        }
+
+        // The text of a block can come in several events, the whole of it replaces the first one
+        let start = i;
+        let mut code = String::new();
+        while let Some(Event::Text(text)) = events.get(i) {
+            code.push_str(text);
+            i += 1;
+        }
+        if start == i {
+            continue;
+        }
+
+        let mut html = String::with_capacity(code.len() * 2);
+        for line in code.split_inclusive('\n') {
+            let class = if line.starts_with("@@") {
+                Some("diff-hunk")
+            } else if line.starts_with('+') {
+                Some("diff-added")
+            } else if line.starts_with('-') {
+                Some("diff-removed")
+            } else {
+                None
+            };
+            let (text, newline) = match line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (line, ""),
+            };
+            let text = escape_html(text);
+            match class {
+                Some(class) => html.push_str(&format!(r#"<span class="{class}">{text}</span>{newline}"#)),
+                None => html.push_str(&format!("{text}{newline}")),
+            }
+        }
+
+        events[start] = Event::Html(html.into());
+        for event in &mut events[start + 1..i] {
+            *event = Event::Html("".into());
+        }
+    }
+}
+
+/// Highlights the code blocks of a language syntect knows, the ones whose text is still plain:
+/// diffs and collapsed blocks were already replaced.
+fn highlight_code(events: &mut [Event]) {
+    let mut i = 0;
+    while i < events.len() {
+        let language = match &events[i] {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                info.split([',', ' ']).next().unwrap_or_default().to_string()
+            }
+            _ => String::new(),
+        };
+        i += 1;
+        if language.is_empty() {
+            continue;
+        }
+
+        let start = i;
+        let mut code = String::new();
+        while let Some(Event::Text(text)) = events.get(i) {
+            code.push_str(text);
+            i += 1;
+        }
+        let Some(html) = (start < i).then(|| highlight(&code, &language)).flatten() else {
+            continue;
+        };
+
+        events[start] = Event::Html(html.into());
+        for event in &mut events[start + 1..i] {
+            *event = Event::Html("".into());
+        }
+    }
+}
+
+/// Label of a `filename=` or `title=` key of an info string.
+fn caption_label(key: &str) -> Option<&str> {
+    key.strip_prefix("filename=").or_else(|| key.strip_prefix("title="))
+}
+
+/// Code blocks labeled with a file, like ```` ```rust,filename=src/main.rs ````, `title=` works
+/// too. The label is removed from the info string, and the index of their opening event is mapped
+/// to the label and the index of the closing one.
+fn code_captions(events: &mut [Event]) -> HashMap<usize, (String, usize)> {
+    let mut captions = HashMap::new();
+    let mut open = None;
+
+    for (i, event) in events.iter_mut().enumerate() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                open = info
+                    .split([',', ' '])
+                    .find_map(caption_label)
+                    .map(|label| (i, label.trim_matches('"').to_string()));
+                if open.is_some() {
+                    let rest = info.split([',', ' ']).filter(|key| caption_label(key).is_none()).collect::<Vec<_>>();
+                    *info = rest.join(",").into();
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((start, label)) = open.take() {
+                    captions.insert(start, (label, i));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    captions
+}
+
+/// Wraps the labeled code blocks in a `<figure>` captioned with their file.
+fn with_captions<'a>(
+    events: Vec<Event<'a>>,
+    captions: HashMap<usize, (String, usize)>,
+) -> impl Iterator<Item = Event<'a>> {
+    let ends = captions.values().map(|(_, end)| *end).collect::<Vec<_>>();
+    let mut output = Vec::with_capacity(events.len() + captions.len() * 2);
+
+    for (i, event) in events.into_iter().enumerate() {
+        if let Some((label, _)) = captions.get(&i) {
+            output.push(Event::Html(
+                format!(r#"<figure class="code-block"><figcaption class="code-filename">{}</figcaption>"#, escape_html(label)).into(),
+            ));
+        }
+        output.push(event);
+        if ends.contains(&i) {
+            output.push(Event::Html("</figure>".into()));
+        }
+    }
+
+    output.into_iter()
+}
+
+/// Removes the scripts, event handlers and any other markup outside of the allowlist, keeping
+/// what the renderer itself writes: heading ids, code languages, task list checkboxes, the
+/// tabs of `{{#tabs}}`...
+fn sanitize(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_generic_attributes(["id", "class", "data-source-line", "role", "hidden"])
+        .add_generic_attribute_prefixes(["aria-"])
+        .add_tags(["input", "button"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("img", ["loading", "decoding", "width", "height"])
+        .add_tag_attributes("button", ["type", "tabindex", "data-tab"])
+        .clean(html)
+        .to_string()
+}
+
+/// Turns the `Term` lines followed by `: definition` lines into `<dl>` lists, with their inline
+/// markdown rendered with `options`. A term starts a paragraph, so a colon at the start of a
+/// line inside of one is left alone. Every term and definition keeps its line, the source lines
+/// stay right, and a blank line ends the list. `None` when `content` has no list.
+fn definition_lists(content: &str, options: Options) -> Option<String> {
+    if !content.contains("\n: ") {
+        return None;
+    }
+
+    let lines = content.lines().collect::<Vec<_>>();
+    let is_definition = |index: usize| lines.get(index).is_some_and(|line| line.starts_with(": "));
+    let is_term = |index: usize| {
+        let line = lines[index];
+        !line.trim().is_empty() && !line.starts_with("    ") && !is_definition(index) && is_definition(index + 1)
+    };
+
+    let mut output = Vec::with_capacity(lines.len());
+    let mut fence: Option<&str> = None;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        if let Some(opening) = fence {
+            if closes_fence(line, opening) {
+                fence = None;
+            }
+        } else if let Some(opening) = code_fence(line.trim_start()) {
+            fence = Some(opening);
+        } else if is_term(index) && (index == 0 || lines[index - 1].trim().is_empty()) {
+            let start = output.len();
+            while index < lines.len() && (is_definition(index) || is_term(index)) {
+                let line = lines[index];
+                output.push(match line.strip_prefix(": ") {
+                    Some(definition) => format!("<dd>{}</dd>", inline_html(definition, options)),
+                    None => format!("<dt>{}</dt>", inline_html(line, options)),
+                });
+                index += 1;
+            }
+            output[start].insert_str(0, "<dl>");
+            if let Some(last) = output.last_mut() {
+                last.push_str("</dl>");
+            }
+            continue;
+        }
+        output.push(line.to_string());
+        index += 1;
+    }
+
+    let mut expanded = output.join("\n");
+    if content.ends_with('\n') {
+        expanded.push('\n');
+    }
+    Some(expanded)
+}
+
+/// The opening fence of a code block starting `line`, like ` ``` ` or `~~~~`.
+pub fn code_fence(line: &str) -> Option<&str> {
+    let mark = line.chars().next().filter(|mark| *mark == '`' || *mark == '~')?;
+    let length = line.len() - line.trim_start_matches(mark).len();
+    (length >= 3).then(|| &line[..length])
+}
+
+/// Whether `line` closes the code block opened with `opening`.
+pub fn closes_fence(line: &str, opening: &str) -> bool {
+    let line = line.trim();
+    code_fence(line)
+        .is_some_and(|closing| closing.starts_with(opening) && closing.len() == line.len())
+}
+
+/// HTML of the inline markdown `text`, without the paragraph around it.
+fn inline_html(text: &str, options: Options) -> String {
+    let mut html = String::new();
+    html::push_html(&mut html, Parser::new_ext(text.trim(), options));
+    let html = html.trim_end();
+    html.strip_prefix("<p>")
+        .and_then(|html| html.strip_suffix("</p>"))
+        .unwrap_or(html)
+        .to_string()
+}
+
+/// Plain text of the first `# Heading` of `content`.
+pub fn first_heading(content: &str) -> Option<String> {
+    let events = Parser::new_ext(content, Options::all()).collect::<Vec<_>>();
+    let start = events
+        .iter()
+        .position(|event| matches!(event, Event::Start(Tag::Heading { level: HeadingLevel::H1, .. })))?;
+    let text = heading_text(&events[start + 1..]);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A heading of a chapter, see [`headings`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// 1 for `#`, up to 6.
+    pub level: u8,
+    /// Byte offset of the heading in the markdown.
+    pub start: usize,
+    /// Id [`render_markdown`] gives to the heading.
+    pub id: String,
+    /// Plain text of the heading.
+    pub text: String,
+}
+
+/// Headings of `content`, in order, with their anchors in `anchor_style`.
+pub fn headings(content: &str, anchor_style: AnchorStyle) -> Vec<Heading> {
+    let (events, offsets): (Vec<_>, Vec<_>) =
+        Parser::new_ext(content, Options::all()).into_offset_iter().unzip();
+    let mut used_ids = HashMap::new();
+    let mut headings = Vec::new();
+
+    for i in 0..events.len() {
+        let Event::Start(Tag::Heading { level, id, .. }) = &events[i] else {
+            continue;
+        };
+        let text = heading_text(&events[i + 1..]).trim().to_string();
+        let base = match id {
+            Some(id) => id.to_string(),
+            None => slugify(&text, anchor_style),
+        };
+        headings.push(Heading {
+            level: *level as u8,
+            start: offsets[i].start,
+            id: unique_id(base, &mut used_ids),
+            text,
+        });
+    }
+
+    headings
+}
+
+/// Points the links to the anchors of `content` (`#anchor`) at `target(anchor)` instead, the
+/// ones it returns `None` for are left alone. Only inline links are changed, the destinations
+/// of reference definitions aren't.
+pub fn retarget_anchor_links(content: &str, target: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut copied = 0;
+
+    for (event, range) in Parser::new_ext(content, Options::all()).into_offset_iter() {
+        let Event::Start(Tag::Link { dest_url, .. }) = event else {
+            continue;
+        };
+        let Some(anchor) = dest_url.strip_prefix('#') else {
+            continue;
+        };
+        let Some(url) = target(anchor) else {
+            continue;
+        };
+        // The destination follows the text, `[text](#anchor)`
+        let Some(position) = content[range.clone()].rfind(&format!("(#{anchor}")) else {
+            continue;
+        };
+        let start = range.start + position + 1;
+        output.push_str(&content[copied..start]);
+        output.push_str(&url);
+        copied = start + anchor.len() + 1;
+    }
+
+    output.push_str(&content[copied..]);
+    output
+}
+
+/// Marks the end of the excerpt of a chapter, see [`excerpt_markdown`].
+pub const MORE_MARKER: &str = "<!-- more -->";
+
+/// Markdown of the excerpt of a chapter: everything before [`MORE_MARKER`], or its first
+/// paragraph without one.
+pub fn excerpt_markdown(content: &str) -> Option<&str> {
+    if let Some((excerpt, _)) = content.split_once(MORE_MARKER) {
+        let excerpt = excerpt.trim();
+        return (!excerpt.is_empty()).then_some(excerpt);
+    }
+
+    let mut start = None;
+    for (event, range) in Parser::new_ext(content, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Paragraph) => start = Some(range.start),
+            Event::End(TagEnd::Paragraph) => {
+                let paragraph = content[start.unwrap_or(range.start)..range.end].trim();
+                if !paragraph.is_empty() {
+                    return Some(paragraph);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Plain text of the first paragraph of `content`, cut at a word boundary to fit in `max_chars`.
+pub fn excerpt(content: &str, max_chars: usize) -> Option<String> {
+    let mut text = String::new();
+    let mut in_paragraph = false;
+
+    for event in Parser::new_ext(content, Options::all()) {
+        match event {
+            Event::Start(Tag::Paragraph) => in_paragraph = true,
+            Event::End(TagEnd::Paragraph) if !text.trim().is_empty() => break,
+            Event::End(TagEnd::Paragraph) => in_paragraph = false,
+            Event::Text(fragment) | Event::Code(fragment) if in_paragraph => text.push_str(&fragment),
+            Event::SoftBreak | Event::HardBreak if in_paragraph => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= max_chars {
+        return Some(text);
+    }
+
+    let mut cut = String::new();
+    for word in text.split(' ') {
+        if cut.chars().count() + word.chars().count() + 1 > max_chars.saturating_sub(1) {
+            break;
+        }
+        if !cut.is_empty() {
+            cut.push(' ');
+        }
+        cut.push_str(word);
+    }
+    cut.push('…');
+    Some(cut)
+}
+
+/// Plain text of `content`, code included, with its whitespace collapsed. What a search index
+/// matches the queries against.
+pub fn plain_text(content: &str) -> String {
+    let mut text = String::with_capacity(content.len());
+    for event in Parser::new_ext(content, Options::all()) {
+        match event {
+            Event::Text(fragment) | Event::Code(fragment) => text.push_str(&fragment),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Markdown of the section under the heading with the `anchor` id, up to the next heading of
+/// the same or a higher level. Ids are the ones [`render_markdown`] gives to the headings.
+pub fn section<'a>(
+    content: &'a str,
+    anchor: &str,
+    settings: &MarkdownSettings,
+) -> Option<&'a str> {
+    let (events, offsets): (Vec<_>, Vec<_>) =
+        Parser::new_ext(content, settings.options()).into_offset_iter().unzip();
+    let mut used_ids = HashMap::new();
+    let mut start = None;
+
+    for i in 0..events.len() {
+        let Event::Start(Tag::Heading { level, id, .. }) = &events[i] else {
+            continue;
+        };
+        let level = *level as u8;
+        if let Some((begin, section_level)) = start {
+            if level <= section_level {
+                return Some(&content[begin..offsets[i].start]);
+            }
+            continue;
+        }
+
+        let base = match id {
+            Some(id) => id.to_string(),
+            None => slugify(&heading_text(&events[i + 1..]), settings.anchor_style),
+        };
+        if unique_id(base, &mut used_ids) == anchor {
+            start = Some((offsets[i].start, level));
+        }
+    }
+
+    start.map(|(begin, _)| &content[begin..])
+}
+
+/// Opening tag of a block element carrying the markdown line it comes from, `None` for the
+/// elements left to pulldown-cmark. The closing tags are still written by pulldown-cmark.
+fn open_tag_with_line(tag: &Tag, line: usize) -> Option<String> {
+    let line = format!(r#" data-source-line="{line}""#);
+
+    let html = match tag {
+        Tag::Paragraph => format!("<p{line}>"),
+        Tag::Heading {
+            level,
+            id,
+            classes,
+            attrs,
+        } => {
+            let mut attributes = String::new();
+            if let Some(id) = id {
+                attributes.push_str(&format!(r#" id="{}""#, escape_attr(id)));
+            }
+            if !classes.is_empty() {
+                let classes = classes.iter().map(|class| class.as_ref()).collect::<Vec<_>>();
+                attributes.push_str(&format!(r#" class="{}""#, escape_attr(&classes.join(" "))));
+            }
+            for (key, value) in attrs {
+                let value = value.as_deref().unwrap_or_default();
+                attributes.push_str(&format!(r#" {}="{}""#, escape_attr(key), escape_attr(value)));
+            }
+            format!("<{level}{attributes}{line}>")
+        }
+        Tag::List(None) => format!("<ul{line}>"),
+        Tag::List(Some(1)) => format!("<ol{line}>"),
+        Tag::List(Some(start)) => format!(r#"<ol start="{start}"{line}>"#),
+        Tag::CodeBlock(CodeBlockKind::Fenced(info)) => {
+            match info.split([',', ' ']).next().unwrap_or_default() {
+                "" => format!("<pre{line}><code>"),
+                lang => format!(r#"<pre{line}><code class="language-{}">"#, escape_attr(lang)),
+            }
+        }
+        Tag::CodeBlock(CodeBlockKind::Indented) => format!("<pre{line}><code>"),
+        _ => return None,
+    };
+
+    Some(html)
+}
+
+pub fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Replaces the known `:shortcode:`s of `text` with their emoji, unknown ones are kept.
+fn replace_shortcodes(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(':') {
+        let after = &rest[start + 1..];
+        let emoji = after.find(':').and_then(|end| {
+            let name = &after[..end];
+            let is_shortcode = !name.is_empty()
+                && name.chars().all(|ch| ch.is_ascii_alphanumeric() || "_+-".contains(ch));
+            is_shortcode
+                .then(|| emojis::get_by_shortcode(name))
+                .flatten()
+                .map(|emoji| (emoji, end))
+        });
+
+        match emoji {
+            Some((emoji, end)) => {
+                output.push_str(&rest[..start]);
+                output.push_str(emoji.as_str());
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push_str(&rest[..=start]);
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Plain text of the heading starting right before `events`.
+fn heading_text(events: &[Event]) -> String {
+    events
+        .iter()
+        .take_while(|event| !matches!(event, Event::End(TagEnd::Heading(_))))
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Turns a heading into an anchor in `style`: alphanumerics, `_` and `-` are kept lowercased,
+/// whitespace becomes `-` and everything else is dropped, see [`AnchorStyle`] for how they differ.
+pub fn slugify(text: &str, style: AnchorStyle) -> String {
+    match style {
+        AnchorStyle::Mdbook => text
+            .chars()
+            .filter_map(|ch| {
+                if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+                    Some(ch.to_ascii_lowercase())
+                } else if ch.is_whitespace() {
+                    Some('-')
+                } else {
+                    None
+                }
+            })
+            .collect(),
+        AnchorStyle::Github => text
+            .to_lowercase()
+            .chars()
+            .filter_map(|ch| match ch {
+                ' ' => Some('-'),
+                ch if ch.is_alphanumeric() || ch == '_' || ch == '-' => Some(ch),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+/// Appends `-1`, `-2`, ... to ids that were already used.
+fn unique_id(id: String, used_ids: &mut HashMap<String, usize>) -> String {
+    let count = used_ids.entry(id.clone()).or_insert(0);
+    let unique = if *count == 0 {
+        id
+    } else {
+        format!("{id}-{count}")
+    };
+    *count += 1;
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchor_styles_differ_on_case_and_whitespace() {
+        assert_eq!(slugify("Über Uns?", AnchorStyle::Mdbook), "Über-uns");
+        assert_eq!(slugify("Über Uns?", AnchorStyle::Github), "über-uns");
+        assert_eq!(slugify("a\tb", AnchorStyle::Mdbook), "a-b");
+        assert_eq!(slugify("a\tb", AnchorStyle::Github), "ab");
+        assert_eq!(slugify("What's `new`, v2.0", AnchorStyle::Github), "whats-new-v20");
+    }
+
+    #[test]
+    fn the_section_number_goes_in_the_first_title() {
+        let html = "<p>Intro</p>\n<h1 id=\"setup\">Setup</h1>\n<h1 id=\"more\">More</h1>\n";
+
+        assert_eq!(
+            number_heading(html, "1.2."),
+            "<p>Intro</p>\n<h1 id=\"setup\"><span class=\"section-number\">1.2.</span> Setup</h1>\n\
+             <h1 id=\"more\">More</h1>\n"
+        );
+        assert_eq!(number_heading("<h2>Setup</h2>", "1."), "<h2>Setup</h2>");
+    }
+
+    #[test]
+    fn shortcodes_with_underscores_are_replaced() {
+        let settings = MarkdownSettings {
+            emoji: true,
+            ..Default::default()
+        };
+        let html = render_markdown(
+            "Done :white_check_mark:, :not_an_emoji: and `:smile:`",
+            None,
+            &settings,
+        );
+
+        assert!(html.contains("Done ✅, :not_an_emoji: and <code>:smile:</code>"));
+    }
+
+    #[test]
+    fn terms_followed_by_definitions_become_lists() {
+        let markdown = "Intro\n\nApple\n: A *fruit*\n: A company\nPear\n: Another fruit\n\nAfter\n";
+
+        assert_eq!(
+            definition_lists(markdown, Options::empty()).unwrap(),
+            "Intro\n\n<dl><dt>Apple</dt>\n<dd>A <em>fruit</em></dd>\n<dd>A company</dd>\n\
+             <dt>Pear</dt>\n<dd>Another fruit</dd></dl>\n\nAfter\n"
+        );
+    }
+
+    #[test]
+    fn definition_lists_skip_paragraphs_and_code_blocks() {
+        // A colon continuing a paragraph isn't a definition
+        assert_eq!(
+            definition_lists("First line\nTerm\n: not a definition\n", Options::empty()),
+            Some("First line\nTerm\n: not a definition\n".to_string())
+        );
+        assert_eq!(
+            definition_lists("```\nTerm\n: code\n```\n", Options::empty()),
+            Some("```\nTerm\n: code\n```\n".to_string())
+        );
+        assert_eq!(definition_lists("No list: here\n", Options::empty()), None);
+    }
+
+    #[test]
+    fn definition_lists_are_opt_in() {
+        let markdown = "Term\n: Definition\n";
+        assert!(!render_markdown(markdown, None, &MarkdownSettings::default()).contains("<dl>"));
+
+        let mut settings = MarkdownSettings::default();
+        settings.extensions.definition_lists = true;
+        assert!(render_markdown(markdown, None, &settings)
+            .contains("<dl><dt>Term</dt>\n<dd>Definition</dd></dl>"));
+    }
+
+    #[test]
+    fn code_fences_close_with_the_same_mark_and_no_info() {
+        assert_eq!(code_fence("```rust"), Some("```"));
+        assert_eq!(code_fence("~~~~"), Some("~~~~"));
+        assert_eq!(code_fence("``"), None);
+        assert!(closes_fence("````", "```"));
+        assert!(closes_fence("  ```  ", "```"));
+        assert!(!closes_fence("```", "````"));
+        assert!(!closes_fence("~~~", "```"));
+        assert!(!closes_fence("```rust", "```"));
+    }
+
+    #[test]
+    fn code_blocks_over_the_limit_are_collapsed_and_escaped() {
+        let settings = MarkdownSettings {
+            large_code_block: 20,
+            ..Default::default()
+        };
+        let markdown = "```rust\nlet a = \"<b>\";\nlet b = 1;\n```\n\n```rust\nlet c;\n```\n";
+        let html = render_markdown(markdown, None, &settings);
+
+        assert!(html.contains(
+            "<details class=\"large-code-block\"><summary>2 lines</summary><pre><code>\
+             let a = \"&lt;b&gt;\";\nlet b = 1;\n</code></pre></details>"
+        ));
+        // The small block is still highlighted
+        assert_eq!(html.matches("large-code-block").count(), 1);
+        assert!(html.contains("syn-"));
+    }
+
+    #[test]
+    fn the_largest_code_block_over_the_limit_is_reported() {
+        let content = "```\n12345\n```\n\n    1234567890\n\n`inline code is not a block`\n";
+
+        assert_eq!(largest_code_block(content, 5), Some(11));
+        assert_eq!(largest_code_block(content, 6), Some(11));
+        assert_eq!(largest_code_block(content, 11), None);
+        assert_eq!(largest_code_block("No code\n", 0), None);
+    }
+
+    #[test]
+    fn id_prefix_applies_to_headings_and_their_links() {
+        let markdown =
+            "# Intro\n\n## Intro\n\nSee [the start](#intro) or [a site](https://a.b/#intro).";
+        let html = render_markdown(markdown, Some("setup"), &MarkdownSettings::default());
+
+        assert!(html.contains(r#"<h1 id="setup-intro">"#));
+        assert!(html.contains(r#"<h2 id="setup-intro-1">"#));
+        assert!(html.contains(r##"href="#setup-intro""##));
+        assert!(html.contains(r##"href="https://a.b/#intro""##));
+
+        let html = render_markdown(markdown, None, &MarkdownSettings::default());
+        assert!(html.contains(r#"<h1 id="intro">"#));
+        assert!(html.contains(r##"href="#intro""##));
+    }
+}
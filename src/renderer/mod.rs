@@ -1,3 +1,8 @@
 pub mod async_component;
+pub mod dates;
+pub mod highlight;
+pub mod markdown;
+pub mod output;
 pub mod render;
+pub mod rewrite;
 pub mod ssg;
@@ -0,0 +1,80 @@
+//! Formats other than HTML a book can be written in, each one enabled by its `[output.<name>]`
+//! section of `book.toml`.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::models::book::Book;
+
+/// Writes a loaded book in one format.
+pub trait Renderer {
+    /// Name of the `[output.<name>]` section enabling the renderer.
+    fn name(&self) -> &'static str;
+
+    /// Writes `book` to the `out` directory, which exists.
+    fn render(&self, book: &Book, out: &Path) -> Result<()>;
+}
+
+/// The renderer of the `[output.<name>]` section, `None` for the unknown ones. HTML isn't one
+/// of them, its build is asynchronous and reports its timings.
+pub fn renderer(name: &str) -> Option<Box<dyn Renderer>> {
+    match name {
+        "markdown" => Some(Box::new(MarkdownBundle)),
+        "json" => Some(Box::new(JsonDump)),
+        _ => None,
+    }
+}
+
+/// The markdown of the chapters, one folder per language with a `SUMMARY.md` listing them in
+/// order.
+pub struct MarkdownBundle;
+
+impl Renderer for MarkdownBundle {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn render(&self, book: &Book, out: &Path) -> Result<()> {
+        for section in &book.sections {
+            let folder = out.join(&section.language);
+            fs::create_dir_all(&folder)
+                .with_context(|| format!("Unable to create {}", folder.display()))?;
+
+            let mut summary = String::from("# Summary\n\n");
+            for chapter in &section.chapters {
+                let Some(slug) = &chapter.slug else { continue };
+                let file = format!("{slug}.md");
+                summary.push_str(&format!("- [{}]({file})\n", chapter.title));
+                fs::write(folder.join(&file), chapter.content.as_deref().unwrap_or_default())
+                    .with_context(|| format!("Unable to write {file}"))?;
+            }
+            fs::write(folder.join("SUMMARY.md"), summary).with_context(|| "Unable to write SUMMARY.md")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `book.json`, the configuration and the chapters of every language.
+pub struct JsonDump;
+
+impl Renderer for JsonDump {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn render(&self, book: &Book, out: &Path) -> Result<()> {
+        let sections = book
+            .sections
+            .iter()
+            .map(|section| json!({ "language": section.language, "chapters": section.chapters }))
+            .collect::<Vec<_>>();
+        let dump = json!({ "config": book.config, "sections": sections });
+
+        fs::write(out.join("book.json"), serde_json::to_string_pretty(&dump)?)
+            .with_context(|| "Unable to write book.json")
+    }
+}
@@ -1,19 +1,40 @@
-use anyhow::anyhow;
 use std::path::Path;
 use tokio::fs;
 
 use leptos::{provide_context, IntoView};
 
+use crate::default_theme::overrides::ThemeOverrides;
+use crate::diagnostic::Diagnostic;
+use crate::models::preprocessors_config::Analytics;
+use crate::renderer::dates::DateSettings;
+use crate::renderer::markdown::MarkdownSettings;
 use crate::renderer::render::render;
+use crate::renderer::rewrite::{rewrite_page, RewriteSettings};
 
 pub struct Ssg<'a> {
     out_dir: &'a Path,
+    site: SiteContext,
 }
 
 impl<'a> Ssg<'a> {
     #[must_use]
     pub fn new(out_dir: &'a Path) -> Self {
-        Self { out_dir }
+        Self {
+            out_dir,
+            site: SiteContext::default(),
+        }
+    }
+
+    /// Shares the values computed once per build with every page generated by this `Ssg`.
+    #[must_use]
+    pub fn with_site(mut self, site: SiteContext) -> Self {
+        self.site = site;
+        self
+    }
+
+    /// Values shared with every page generated by this `Ssg`.
+    pub fn site(&self) -> &SiteContext {
+        &self.site
     }
 
     pub async fn gen<F, V>(
@@ -27,17 +48,31 @@ impl<'a> Ssg<'a> {
     {
         // SsgContext will be available to all components in the view
         let ssg_ctx = SsgContext { path: path.clone() };
+        let site_ctx = self.site.clone();
 
         // Render the view to a string
-        let res = render(move || view().into_view(), move || provide_context(ssg_ctx)).await;
+        let res = render(move || view().into_view(), move || {
+            provide_context(ssg_ctx);
+            provide_context(site_ctx);
+        })
+        .await;
 
         // Write the string to a file
-        let out_file = self.out_dir.join(path);
+        let out_file = self.out_dir.join(&path);
+        let page_dir = out_file.parent().unwrap_or(self.out_dir);
+        fs::create_dir_all(page_dir).await?;
+        // Pages written as `page/index.html` are a directory deeper than their markdown
+        let base = "../".repeat(path.matches('/').count());
+        let mut missing_images = Vec::new();
+        let res = rewrite_page(&res, page_dir, &base, &self.site.rewrite, &mut missing_images)?;
+        for src in missing_images {
+            Diagnostic::warning(format!("the image `{src}` of `{path}` doesn't exist")).emit();
+        }
         if let Err(error) = fs::write(&out_file, res).await {
-            println!("y si, fallo, que esperabas?, {}", error);
+            log::error!("Unable to write {}: {}", out_file.display(), error);
             Err(error)?;
         };
-        println!("wrote {}", out_file.display());
+        log::debug!("wrote {}", out_file.display());
 
         Ok(())
     }
@@ -47,3 +82,56 @@ impl<'a> Ssg<'a> {
 pub struct SsgContext {
     pub path: String,
 }
+
+/// Values computed once per build and shared with every generated page.
+#[derive(Debug, Clone, Default)]
+pub struct SiteContext {
+    /// Rendered HTML of the global footer, see `[output.html] footer`.
+    pub footer: Option<String>,
+    /// Trusted markup added to the `<head>` of every page, see `[output.html] additional-head`.
+    pub additional_head: Option<String>,
+    /// Files of the book's theme directory replacing the bundled ones.
+    pub theme: ThemeOverrides,
+    /// Location of the stylesheet linked by every page, `None` when no stylesheet is written.
+    pub stylesheet: Option<String>,
+    /// Settings of the markdown renderer.
+    pub markdown: MarkdownSettings,
+    /// Settings of the rewriting applied to the generated HTML.
+    pub rewrite: RewriteSettings,
+    /// Style of the links to the generated pages, see [`crate::default_theme::links`].
+    pub trailing_slash: Option<bool>,
+    /// Language written to the root of the output instead of its own directory, see
+    /// `[output.html] default-language-in-subdir`.
+    pub root_language: Option<String>,
+    /// Whether the chapters show a reading progress bar, see `[output.html] reading-progress`.
+    pub reading_progress: bool,
+    /// Whether clicking the images of the chapters zooms them, see `[output.html] lightbox`.
+    pub lightbox: bool,
+    /// Rust edition the code blocks run with on the playground, `None` when they can't be run,
+    /// see `[output.html.playground] runnable`.
+    pub playground_edition: Option<String>,
+    /// Whether the pages register the offline service worker, see `[output.html] offline`.
+    pub offline: bool,
+    /// Analytics script of every page, see `[output.html] analytics`.
+    pub analytics: Option<Analytics>,
+    /// Whether the analytics script waits for the consent of the reader, see
+    /// `[output.html] consent`.
+    pub consent: bool,
+    /// Whether the pages ask search engines not to index them, see `[output.html] robots`.
+    pub no_index: bool,
+    /// How the dates are shown, see `[output.html] date-format`.
+    pub dates: DateSettings,
+    /// URL of the font every page preloads, see `[output.html.fonts]`.
+    pub font_preload: Option<String>,
+}
+
+impl SiteContext {
+    /// Directory of the pages of `language` in the output, empty for the root.
+    pub fn language_dir<'a>(&self, language: &'a str) -> &'a str {
+        if self.root_language.as_deref() == Some(language) {
+            ""
+        } else {
+            language
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Builds the fixture book of `tests/fixtures/book` and checks the pages written.
+
+use std::fs;
+use std::path::Path;
+
+use mdbook_killer::commands::build_book;
+use mdbook_killer::error::BuildError;
+
+/// Contents of the `<title>` element of `html`, leptos adds attributes to the tag.
+fn title(html: &str) -> &str {
+    let tag = html.find("<title").expect("the page has no <title>");
+    let start = tag + html[tag..].find('>').expect("the <title> tag isn't closed") + 1;
+    let end = html[start..].find("</title>").expect("the <title> isn't closed");
+    &html[start..start + end]
+}
+
+#[tokio::test]
+async fn builds_the_fixture_book() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/book");
+    let out = tempfile::tempdir().unwrap();
+
+    build_book(&root, out.path()).await.unwrap();
+
+    for file in ["index.html", "README.html", "getting-started.html", "404.html"] {
+        assert!(out.path().join(file).is_file(), "{file} wasn't written");
+    }
+
+    let chapter = fs::read_to_string(out.path().join("getting-started.html")).unwrap();
+    assert!(title(&chapter).contains("Fixture Book"));
+    assert!(chapter.contains(r#"id="getting-started""#));
+    assert!(chapter.contains("How to build the fixture book."));
+
+    let homepage = fs::read_to_string(out.path().join("index.html")).unwrap();
+    assert!(title(&homepage).contains("Fixture Book"));
+    assert!(homepage.contains("The landing page of the fixture book."));
+}
+
+#[tokio::test]
+async fn a_missing_book_is_a_typed_error() {
+    let root = tempfile::tempdir().unwrap();
+    let out = tempfile::tempdir().unwrap();
+
+    let error = build_book(root.path(), out.path()).await.unwrap_err();
+    assert!(matches!(error, BuildError::ConfigNotFound(path) if path.ends_with("book.toml")));
+}
+
+#[tokio::test]
+async fn a_page_that_cant_be_written_is_an_io_error() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/book");
+    let out = tempfile::tempdir().unwrap();
+    // A directory where the page of the chapter goes
+    fs::create_dir(out.path().join("getting-started.html")).unwrap();
+
+    let error = build_book(&root, out.path()).await.unwrap_err();
+    assert!(matches!(&error, BuildError::Io(_)), "expected an I/O error, got {error:?}");
+    assert!(error.to_string().contains("getting-started.html"));
+}